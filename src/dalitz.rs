@@ -1,45 +1,120 @@
+use std::{fmt::Display, str::FromStr};
+
 use pyo3::prelude::*;
 use rayon::prelude::*;
 use rustitude_core::prelude::*;
 
-#[derive(Default)]
+use crate::{
+    errors::GluexError,
+    utils::{breakup_momentum, sqrt_abs_gradient, Gradient, PyGradient},
+};
+
+// Daughter indices are given in the order [π⁰, π⁺, π⁻] (or [η, π⁺, π⁻] for η′ → ηπ⁺π⁻).
+fn daughters(event: &Event, indices: &[usize; 3]) -> [FourMomentum; 3] {
+    indices.map(|i| event.daughter_p4s[i])
+}
+
+fn check_daughters(indices: &[usize; 3], dataset: &Dataset) -> Result<(), GluexError> {
+    if indices[0] == indices[1] || indices[0] == indices[2] || indices[1] == indices[2] {
+        return Err(GluexError::DuplicateDaughterIndices(*indices));
+    }
+    let index = indices.iter().copied().max().unwrap_or_default();
+    if let Some(event) = dataset
+        .events
+        .read()
+        .iter()
+        .find(|event| index >= event.daughter_p4s.len())
+    {
+        return Err(GluexError::DaughterIndexOutOfRange {
+            index,
+            daughters: event.daughter_p4s.len(),
+        });
+    }
+    Ok(())
+}
+
+// Kinetic energies of the daughters in the parent rest frame along with Q = M - Σm = ΣT.
+fn kinetic_energies(p4s: &[FourMomentum; 3]) -> ([f64; 3], f64) {
+    let parent: FourMomentum = p4s.iter().sum();
+    let t = p4s.map(|p4| p4.boost_along(&parent).e() - p4.m());
+    (t, t.iter().sum())
+}
+
+// X = √3(t - u)/D and Y = 3(s_c - s)/D, with s = (p⁺ + p⁻)², t = (p⁺ + p⁰)², u = (p⁻ + p⁰)²,
+// D = 2M(M - 2m₊ - m₀) and s_c = (M² + Σm²)/3.
+fn dalitz_xy(pi0: &FourMomentum, pip: &FourMomentum, pim: &FourMomentum) -> (f64, f64) {
+    let parent = *pi0 + *pip + *pim;
+
+    let dalitz_s = (*pip + *pim).m2();
+    let dalitz_t = (*pip + *pi0).m2();
+    let dalitz_u = (*pim + *pi0).m2();
+
+    let m3pi = (2.0 * pip.m()) + pi0.m();
+    let dalitz_d = 2.0 * parent.m() * (parent.m() - m3pi);
+    let dalitz_sc = (1.0 / 3.0) * (parent.m2() + pip.m2() + pim.m2() + pi0.m2());
+    let dalitz_x = f64::sqrt(3.0) * (dalitz_t - dalitz_u) / dalitz_d;
+    let dalitz_y = 3.0 * (dalitz_sc - dalitz_s) / dalitz_d;
+    (dalitz_x, dalitz_y)
+}
+
+// |p⁺ × p⁻|² in the parent rest frame, normalized to its maximum in the isospin limit.
+fn normalized_lambda(pi0: &FourMomentum, pip: &FourMomentum, pim: &FourMomentum) -> f64 {
+    let parent = *pi0 + *pip + *pim;
+    let pip_parent = pip.boost_along(&parent);
+    let pim_parent = pim.boost_along(&parent);
+    let pi_cross = pip_parent.momentum().cross(&pim_parent.momentum());
+    (4.0 / 3.0) * f64::abs(pi_cross.dot(&pi_cross))
+        / ((1.0 / 9.0) * (parent.m2() - (2.0 * pip.m() + pi0.m()).powi(2)).powi(2))
+}
+
+// Intensities of the form 1 + Σ p_k f_k, where the f_k are precalculated per event.
+fn polynomial_intensity(parameters: &[f64], terms: &[f64]) -> f64 {
+    1.0 + terms
+        .iter()
+        .zip(parameters)
+        .map(|(term, p)| term * p)
+        .sum::<f64>()
+}
+
 pub struct OmegaDalitz {
+    indices: [usize; 3],
     dalitz_z: Vec<f64>,
     dalitz_sin3theta: Vec<f64>,
     lambda: Vec<f64>,
 }
 
+impl OmegaDalitz {
+    pub fn new(indices: [usize; 3]) -> Self {
+        Self {
+            indices,
+            dalitz_z: Vec::default(),
+            dalitz_sin3theta: Vec::default(),
+            lambda: Vec::default(),
+        }
+    }
+}
+
+impl Default for OmegaDalitz {
+    fn default() -> Self {
+        Self::new([0, 1, 2])
+    }
+}
+
 impl Node for OmegaDalitz {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        check_daughters(&self.indices, dataset)?;
         (self.dalitz_z, (self.dalitz_sin3theta, self.lambda)) = dataset
             .events
             .read()
             .par_iter()
             .map(|event| {
-                let pi0 = event.daughter_p4s[0];
-                let pip = event.daughter_p4s[1];
-                let pim = event.daughter_p4s[2];
-                let omega = pi0 + pip + pim;
-
-                let dalitz_s = (pip + pim).m2();
-                let dalitz_t = (pip + pi0).m2();
-                let dalitz_u = (pim + pi0).m2();
-
-                let m3pi = (2.0 * pip.m()) + pi0.m();
-                let dalitz_d = 2.0 * omega.m() * (omega.m() - m3pi);
-                let dalitz_sc = (1.0 / 3.0) * (omega.m2() + pip.m2() + pim.m2() + pi0.m2());
-                let dalitz_x = f64::sqrt(3.0) * (dalitz_t - dalitz_u) / dalitz_d;
-                let dalitz_y = 3.0 * (dalitz_sc - dalitz_s) / dalitz_d;
+                let [pi0, pip, pim] = daughters(event, &self.indices);
+                let (dalitz_x, dalitz_y) = dalitz_xy(&pi0, &pip, &pim);
 
                 let dalitz_z = dalitz_x * dalitz_x + dalitz_y * dalitz_y;
                 let dalitz_sin3theta = f64::sin(3.0 * f64::asin(dalitz_y / f64::sqrt(dalitz_z)));
 
-                let pip_omega = pip.boost_along(&omega);
-                let pim_omega = pim.boost_along(&omega);
-                let pi_cross = pip_omega.momentum().cross(&pim_omega.momentum());
-
-                let lambda = (4.0 / 3.0) * f64::abs(pi_cross.dot(&pi_cross))
-                    / ((1.0 / 9.0) * (omega.m2() - (2.0 * pip.m() + pi0.m()).powi(2)).powi(2));
+                let lambda = normalized_lambda(&pi0, &pip, &pim);
 
                 (dalitz_z, (dalitz_sin3theta, lambda))
             })
@@ -76,12 +151,592 @@ impl Node for OmegaDalitz {
     }
 }
 
+impl Gradient for OmegaDalitz {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let dalitz_z = self.dalitz_z[event.index];
+        let dalitz_sin3theta = self.dalitz_sin3theta[event.index];
+        let lambda = self.lambda[event.index];
+        let dw = [
+            lambda * 2.0 * dalitz_z,
+            lambda * 2.0 * dalitz_z.powf(3.0 / 2.0) * dalitz_sin3theta,
+            lambda * 2.0 * dalitz_z.powi(2),
+            lambda * 2.0 * dalitz_z.powf(5.0 / 2.0) * dalitz_sin3theta,
+        ];
+        let w = lambda + dw.iter().zip(parameters).map(|(d, p)| d * p).sum::<f64>();
+        Ok(sqrt_abs_gradient(w, &dw))
+    }
+}
+
+// η → π⁺π⁻π⁰ with |A|² = 1 + aY + bY² + cX + dX² + eXY + fY³ + gX²Y + hXY² + lX³, where
+// X = √3(T⁺ - T⁻)/Q and Y = 3T⁰/Q - 1. The terms odd in X violate C.
+pub struct EtaDalitz {
+    indices: [usize; 3],
+    terms: Vec<[f64; 9]>,
+}
+
+impl EtaDalitz {
+    pub fn new(indices: [usize; 3]) -> Self {
+        Self {
+            indices,
+            terms: Vec::default(),
+        }
+    }
+}
+
+impl Default for EtaDalitz {
+    fn default() -> Self {
+        Self::new([0, 1, 2])
+    }
+}
+
+impl Node for EtaDalitz {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        check_daughters(&self.indices, dataset)?;
+        self.terms = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let ([t0, tp, tm], q) = kinetic_energies(&daughters(event, &self.indices));
+                let x = f64::sqrt(3.0) * (tp - tm) / q;
+                let y = 3.0 * t0 / q - 1.0;
+                [
+                    y,
+                    y * y,
+                    x,
+                    x * x,
+                    x * y,
+                    y.powi(3),
+                    x * x * y,
+                    x * y * y,
+                    x.powi(3),
+                ]
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        Ok(f64::sqrt(f64::abs(polynomial_intensity(
+            parameters,
+            &self.terms[event.index],
+        )))
+        .into())
+    }
+
+    fn parameters(&self) -> Vec<String> {
+        ["a", "b", "c", "d", "e", "f", "g", "h", "l"]
+            .map(str::to_string)
+            .to_vec()
+    }
+}
+
+impl Gradient for EtaDalitz {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let terms = &self.terms[event.index];
+        Ok(sqrt_abs_gradient(
+            polynomial_intensity(parameters, terms),
+            terms,
+        ))
+    }
+}
+
+// η → 3π⁰ with |A|² = 1 + 2αZ + 2βZ^{3/2}sin(3φ) + 2γZ², where Z = ⅔Σ(3Tᵢ/Q - 1)². Both Z and
+// Z^{3/2}sin(3φ) = 3X²Y - Y³ are symmetric under permutations of the three π⁰s.
+pub struct EtaNeutralDalitz {
+    indices: [usize; 3],
+    terms: Vec<[f64; 3]>,
+}
+
+impl EtaNeutralDalitz {
+    pub fn new(indices: [usize; 3]) -> Self {
+        Self {
+            indices,
+            terms: Vec::default(),
+        }
+    }
+}
+
+impl Default for EtaNeutralDalitz {
+    fn default() -> Self {
+        Self::new([0, 1, 2])
+    }
+}
+
+impl Node for EtaNeutralDalitz {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        check_daughters(&self.indices, dataset)?;
+        self.terms = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let ([t1, t2, t3], q) = kinetic_energies(&daughters(event, &self.indices));
+                let x = f64::sqrt(3.0) * (t1 - t2) / q;
+                let y = 3.0 * t3 / q - 1.0;
+                let z = x * x + y * y;
+                [2.0 * z, 2.0 * (3.0 * x * x * y - y.powi(3)), 2.0 * z * z]
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        Ok(f64::sqrt(f64::abs(polynomial_intensity(
+            parameters,
+            &self.terms[event.index],
+        )))
+        .into())
+    }
+
+    fn parameters(&self) -> Vec<String> {
+        vec!["alpha".to_string(), "beta".to_string(), "gamma".to_string()]
+    }
+}
+
+impl Gradient for EtaNeutralDalitz {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let terms = &self.terms[event.index];
+        Ok(sqrt_abs_gradient(
+            polynomial_intensity(parameters, terms),
+            terms,
+        ))
+    }
+}
+
+// φ → π⁺π⁻π⁰ through ρπ plus a direct term,
+//   A = √λ (BW_ρ⁰(s₊₋) + BW_ρ⁺(s₊₀) + BW_ρ⁻(s₋₀) + a),
+// with P-wave Breit-Wigners BW(s) = m²/(m² - s - imΓ(s)) and a complex contact amplitude a.
+pub struct PhiDalitz {
+    indices: [usize; 3],
+    rho_mass: f64,
+    rho_width: f64,
+    data: Vec<(f64, Complex64)>,
+}
+
+impl PhiDalitz {
+    pub fn new(indices: [usize; 3]) -> Self {
+        Self {
+            indices,
+            rho_mass: 0.77526,
+            rho_width: 0.1491,
+            data: Vec::default(),
+        }
+    }
+
+    pub fn with_rho(mut self, mass: f64, width: f64) -> Self {
+        self.rho_mass = mass;
+        self.rho_width = width;
+        self
+    }
+
+    fn rho(&self, p1: &FourMomentum, p2: &FourMomentum) -> Complex64 {
+        let s = (*p1 + *p2).m2();
+        let m = f64::sqrt(s);
+        let q = breakup_momentum(m, p1.m(), p2.m());
+        let q0 = breakup_momentum(self.rho_mass, p1.m(), p2.m());
+        let width = self.rho_width * (self.rho_mass / m) * (q / q0).powi(3);
+        self.rho_mass.powi(2) / Complex64::new(self.rho_mass.powi(2) - s, -self.rho_mass * width)
+    }
+}
+
+impl Default for PhiDalitz {
+    fn default() -> Self {
+        Self::new([0, 1, 2])
+    }
+}
+
+impl Node for PhiDalitz {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        check_daughters(&self.indices, dataset)?;
+        self.data = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let [pi0, pip, pim] = daughters(event, &self.indices);
+                (
+                    f64::sqrt(normalized_lambda(&pi0, &pip, &pim)),
+                    self.rho(&pip, &pim) + self.rho(&pip, &pi0) + self.rho(&pim, &pi0),
+                )
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        let (sqrt_lambda, rho) = self.data[event.index];
+        Ok(sqrt_lambda * (rho + Complex64::new(parameters[0], parameters[1])))
+    }
+
+    fn parameters(&self) -> Vec<String> {
+        vec!["a re".to_string(), "a im".to_string()]
+    }
+}
+
+impl Gradient for PhiDalitz {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let (sqrt_lambda, _) = self.data[event.index];
+        Ok(vec![
+            Complex64::new(sqrt_lambda, 0.0),
+            Complex64::new(0.0, sqrt_lambda),
+        ])
+    }
+}
+
+// η′ → ηπ⁺π⁻ with |A|² = 1 + aY + bY² + cX + dX², where X = √3(T⁺ - T⁻)/Q and
+// Y = (m_η + 2m_π)/m_π T_η/Q - 1. The indices are given in the order [η, π⁺, π⁻].
+pub struct EtaPrimeDalitz {
+    indices: [usize; 3],
+    terms: Vec<[f64; 4]>,
+}
+
+impl EtaPrimeDalitz {
+    pub fn new(indices: [usize; 3]) -> Self {
+        Self {
+            indices,
+            terms: Vec::default(),
+        }
+    }
+}
+
+impl Default for EtaPrimeDalitz {
+    fn default() -> Self {
+        Self::new([0, 1, 2])
+    }
+}
+
+impl Node for EtaPrimeDalitz {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        check_daughters(&self.indices, dataset)?;
+        self.terms = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let p4s = daughters(event, &self.indices);
+                let ([t_eta, tp, tm], q) = kinetic_energies(&p4s);
+                let (m_eta, m_pi) = (p4s[0].m(), p4s[1].m());
+                let x = f64::sqrt(3.0) * (tp - tm) / q;
+                let y = (m_eta + 2.0 * m_pi) / m_pi * t_eta / q - 1.0;
+                [y, y * y, x, x * x]
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        Ok(f64::sqrt(f64::abs(polynomial_intensity(
+            parameters,
+            &self.terms[event.index],
+        )))
+        .into())
+    }
+
+    fn parameters(&self) -> Vec<String> {
+        ["a", "b", "c", "d"].map(str::to_string).to_vec()
+    }
+}
+
+impl Gradient for EtaPrimeDalitz {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let terms = &self.terms[event.index];
+        Ok(sqrt_abs_gradient(
+            polynomial_intensity(parameters, terms),
+            terms,
+        ))
+    }
+}
+
+// A term in the Dalitz-plot expansion, labeled "x{i}y{j}" for XⁱYʲ, or "r{n}cos{k}" and "r{n}sin{k}"
+// for Z^{n/2}cos(kφ) and Z^{n/2}sin(kφ), where Z = X² + Y² and φ = atan2(Y, X). Omitted exponents
+// default to one (and a bare "r{n}" means k = 0), so ω → 3π's 2βZ^{3/2}sin(3φ) is "r3sin3".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DalitzTerm {
+    Cartesian { x: usize, y: usize },
+    Cosine { r: usize, k: usize },
+    Sine { r: usize, k: usize },
+}
+
+impl DalitzTerm {
+    // The constant 1 is already part of every intensity, and sin(0φ) vanishes.
+    fn is_trivial(&self) -> bool {
+        matches!(
+            *self,
+            Self::Cartesian { x: 0, y: 0 } | Self::Cosine { r: 0, k: 0 } | Self::Sine { k: 0, .. }
+        )
+    }
+
+    fn parse(s: &str) -> Result<Self, GluexError> {
+        let err = || GluexError::ParseDalitzTerm(s.to_string());
+        let label = s.trim().to_lowercase().replace(' ', "");
+        if label.is_empty() {
+            return Err(err());
+        }
+        let exponent = |part: &str, prefix: &str| match part.strip_prefix(prefix) {
+            Some("") => Ok(1),
+            Some(digits) => digits.parse().map_err(|_| err()),
+            None => Err(err()),
+        };
+        if let Some((r, k)) = label.split_once("cos") {
+            return Ok(Self::Cosine {
+                r: exponent(r, "r")?,
+                k: exponent(k, "")?,
+            });
+        }
+        if let Some((r, k)) = label.split_once("sin") {
+            return Ok(Self::Sine {
+                r: exponent(r, "r")?,
+                k: exponent(k, "")?,
+            });
+        }
+        if label.starts_with('r') {
+            return Ok(Self::Cosine {
+                r: exponent(&label, "r")?,
+                k: 0,
+            });
+        }
+        let (x, y) = match label.split_once('y') {
+            Some((x, y)) => (x, Some(y)),
+            None => (label.as_str(), None),
+        };
+        let x = if x.is_empty() { 0 } else { exponent(x, "x")? };
+        let y = match y {
+            Some(y) => exponent(y, "")?,
+            None => 0,
+        };
+        Ok(Self::Cartesian { x, y })
+    }
+
+    pub fn evaluate(&self, x: f64, y: f64) -> f64 {
+        let r = f64::sqrt(x * x + y * y);
+        let phi = y.atan2(x);
+        match *self {
+            Self::Cartesian { x: i, y: j } => x.powi(i as i32) * y.powi(j as i32),
+            Self::Cosine { r: n, k } => r.powi(n as i32) * f64::cos(k as f64 * phi),
+            Self::Sine { r: n, k } => r.powi(n as i32) * f64::sin(k as f64 * phi),
+        }
+    }
+}
+
+impl FromStr for DalitzTerm {
+    type Err = GluexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let term = Self::parse(s)?;
+        if term.is_trivial() {
+            return Err(GluexError::TrivialDalitzTerm(s.to_string()));
+        }
+        Ok(term)
+    }
+}
+
+impl Display for DalitzTerm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cartesian { x, y } => write!(f, "x{}y{}", x, y),
+            Self::Cosine { r, k: 0 } => write!(f, "r{}", r),
+            Self::Cosine { r, k } => write!(f, "r{}cos{}", r, k),
+            Self::Sine { r, k } => write!(f, "r{}sin{}", r, k),
+        }
+    }
+}
+
+// |A|² = 1 + Σ c_t f_t(X, Y) with one coefficient c_t per term, using the variables of
+// `OmegaDalitz`. With `with_p_wave` the polynomial is multiplied by the normalized |p⁺ × p⁻|²
+// of a vector decaying to three pseudoscalars.
+pub struct DalitzPolynomial {
+    indices: [usize; 3],
+    terms: Vec<DalitzTerm>,
+    p_wave: bool,
+    data: Vec<(f64, Vec<f64>)>,
+}
+
+impl DalitzPolynomial {
+    pub fn new(indices: [usize; 3], terms: &[DalitzTerm]) -> Result<Self, GluexError> {
+        for (i, term) in terms.iter().enumerate() {
+            if term.is_trivial() {
+                return Err(GluexError::TrivialDalitzTerm(term.to_string()));
+            }
+            if terms[..i].contains(term) {
+                return Err(GluexError::DuplicateDalitzTerm(term.to_string()));
+            }
+        }
+        Ok(Self {
+            indices,
+            terms: terms.to_vec(),
+            p_wave: false,
+            data: Vec::default(),
+        })
+    }
+
+    pub fn with_p_wave(mut self) -> Self {
+        self.p_wave = true;
+        self
+    }
+}
+
+impl Node for DalitzPolynomial {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        check_daughters(&self.indices, dataset)?;
+        self.data = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let [p0, p1, p2] = daughters(event, &self.indices);
+                let (x, y) = dalitz_xy(&p0, &p1, &p2);
+                let lambda = if self.p_wave {
+                    normalized_lambda(&p0, &p1, &p2)
+                } else {
+                    1.0
+                };
+                (
+                    lambda,
+                    self.terms.iter().map(|term| term.evaluate(x, y)).collect(),
+                )
+            })
+            .collect();
+        Ok(())
+    }
+
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        let (lambda, terms) = &self.data[event.index];
+        Ok(f64::sqrt(f64::abs(lambda * polynomial_intensity(parameters, terms))).into())
+    }
+
+    fn parameters(&self) -> Vec<String> {
+        self.terms.iter().map(DalitzTerm::to_string).collect()
+    }
+}
+
+impl Gradient for DalitzPolynomial {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let (lambda, terms) = &self.data[event.index];
+        let dw: Vec<f64> = terms.iter().map(|term| lambda * term).collect();
+        Ok(sqrt_abs_gradient(
+            lambda * polynomial_intensity(parameters, terms),
+            &dw,
+        ))
+    }
+}
+
 #[pyfunction(name = "OmegaDalitz")]
-fn omega_dalitz(name: &str) -> PyAmpOp {
-    Amplitude::new(name, Box::<OmegaDalitz>::default()).into()
+#[pyo3(signature = (name, indices=[0, 1, 2]))]
+fn omega_dalitz(name: &str, indices: [usize; 3]) -> PyAmpOp {
+    Amplitude::new(name, Box::new(OmegaDalitz::new(indices))).into()
+}
+
+#[pyfunction(name = "OmegaDalitzGradient")]
+#[pyo3(signature = (indices=[0, 1, 2]))]
+fn omega_dalitz_gradient(indices: [usize; 3]) -> PyGradient {
+    PyGradient::new(OmegaDalitz::new(indices))
+}
+
+#[pyfunction(name = "EtaDalitz")]
+#[pyo3(signature = (name, indices=[0, 1, 2]))]
+fn eta_dalitz(name: &str, indices: [usize; 3]) -> PyAmpOp {
+    Amplitude::new(name, Box::new(EtaDalitz::new(indices))).into()
+}
+
+#[pyfunction(name = "EtaDalitzGradient")]
+#[pyo3(signature = (indices=[0, 1, 2]))]
+fn eta_dalitz_gradient(indices: [usize; 3]) -> PyGradient {
+    PyGradient::new(EtaDalitz::new(indices))
+}
+
+#[pyfunction(name = "EtaNeutralDalitz")]
+#[pyo3(signature = (name, indices=[0, 1, 2]))]
+fn eta_neutral_dalitz(name: &str, indices: [usize; 3]) -> PyAmpOp {
+    Amplitude::new(name, Box::new(EtaNeutralDalitz::new(indices))).into()
+}
+
+#[pyfunction(name = "EtaNeutralDalitzGradient")]
+#[pyo3(signature = (indices=[0, 1, 2]))]
+fn eta_neutral_dalitz_gradient(indices: [usize; 3]) -> PyGradient {
+    PyGradient::new(EtaNeutralDalitz::new(indices))
+}
+
+#[pyfunction(name = "PhiDalitz")]
+#[pyo3(signature = (name, indices=[0, 1, 2], rho_mass=0.77526, rho_width=0.1491))]
+fn phi_dalitz(name: &str, indices: [usize; 3], rho_mass: f64, rho_width: f64) -> PyAmpOp {
+    Amplitude::new(
+        name,
+        Box::new(PhiDalitz::new(indices).with_rho(rho_mass, rho_width)),
+    )
+    .into()
+}
+
+#[pyfunction(name = "PhiDalitzGradient")]
+#[pyo3(signature = (indices=[0, 1, 2], rho_mass=0.77526, rho_width=0.1491))]
+fn phi_dalitz_gradient(indices: [usize; 3], rho_mass: f64, rho_width: f64) -> PyGradient {
+    PyGradient::new(PhiDalitz::new(indices).with_rho(rho_mass, rho_width))
+}
+
+#[pyfunction(name = "EtaPrimeDalitz")]
+#[pyo3(signature = (name, indices=[0, 1, 2]))]
+fn eta_prime_dalitz(name: &str, indices: [usize; 3]) -> PyAmpOp {
+    Amplitude::new(name, Box::new(EtaPrimeDalitz::new(indices))).into()
+}
+
+#[pyfunction(name = "EtaPrimeDalitzGradient")]
+#[pyo3(signature = (indices=[0, 1, 2]))]
+fn eta_prime_dalitz_gradient(indices: [usize; 3]) -> PyGradient {
+    PyGradient::new(EtaPrimeDalitz::new(indices))
+}
+
+fn py_dalitz_polynomial_node(
+    terms: &[String],
+    indices: [usize; 3],
+    p_wave: bool,
+) -> PyResult<DalitzPolynomial> {
+    let terms = terms
+        .iter()
+        .map(|term| <DalitzTerm as FromStr>::from_str(term))
+        .collect::<Result<Vec<DalitzTerm>, GluexError>>()?;
+    let node = DalitzPolynomial::new(indices, &terms)?;
+    Ok(if p_wave { node.with_p_wave() } else { node })
+}
+
+#[pyfunction(name = "DalitzPolynomial")]
+#[pyo3(signature = (name, terms, indices=[0, 1, 2], p_wave=false))]
+fn dalitz_polynomial(
+    name: &str,
+    terms: Vec<String>,
+    indices: [usize; 3],
+    p_wave: bool,
+) -> PyResult<PyAmpOp> {
+    Ok(Amplitude::new(
+        name,
+        Box::new(py_dalitz_polynomial_node(&terms, indices, p_wave)?),
+    )
+    .into())
+}
+
+#[pyfunction(name = "DalitzPolynomialGradient")]
+#[pyo3(signature = (terms, indices=[0, 1, 2], p_wave=false))]
+fn dalitz_polynomial_gradient(
+    terms: Vec<String>,
+    indices: [usize; 3],
+    p_wave: bool,
+) -> PyResult<PyGradient> {
+    Ok(PyGradient::new(py_dalitz_polynomial_node(
+        &terms, indices, p_wave,
+    )?))
 }
 
 pub fn pyo3_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(omega_dalitz, m)?)?;
+    m.add_function(wrap_pyfunction!(omega_dalitz_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(eta_dalitz, m)?)?;
+    m.add_function(wrap_pyfunction!(eta_dalitz_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(eta_neutral_dalitz, m)?)?;
+    m.add_function(wrap_pyfunction!(eta_neutral_dalitz_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(phi_dalitz, m)?)?;
+    m.add_function(wrap_pyfunction!(phi_dalitz_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(eta_prime_dalitz, m)?)?;
+    m.add_function(wrap_pyfunction!(eta_prime_dalitz_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(dalitz_polynomial, m)?)?;
+    m.add_function(wrap_pyfunction!(dalitz_polynomial_gradient, m)?)?;
     Ok(())
 }
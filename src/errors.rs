@@ -0,0 +1,92 @@
+use pyo3::{exceptions::PyValueError, PyErr};
+use rustitude_core::prelude::*;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GluexError {
+    #[error("invalid wave (l = {l}, m = {m}): |m| must not exceed l")]
+    InvalidWave { l: usize, m: isize },
+    #[error("unable to parse \"{0}\" as a wave, expected a label like \"D+2\" or \"G-1\"")]
+    ParseWave(String),
+    #[error("invalid Wigner d-matrix indices (2j = {two_j}, 2m = {two_m}, 2n = {two_n}): |m| and |n| must not exceed j and j - m, j - n must be integers")]
+    InvalidSpinProjection {
+        two_j: usize,
+        two_m: isize,
+        two_n: isize,
+    },
+    #[error("invalid projection 2m = {two_m} for 2j = {two_j}: |m| must not exceed j and j - m must be an integer")]
+    InvalidProjection { two_j: usize, two_m: isize },
+    #[error("{0} is not an integer or half-integer")]
+    InvalidHalfInteger(f64),
+    #[error("unknown frame \"{0}\"")]
+    ParseFrame(String),
+    #[error("unknown reflectivity \"{0}\"")]
+    ParseReflectivity(String),
+    #[error("unknown phase space \"{0}\"")]
+    ParsePhaseSpace(String),
+    #[error("channel {channel} is out of range for a K-matrix with {channels} channels")]
+    InvalidChannel { channel: usize, channels: usize },
+    #[error("expected {expected} phase-space entries, got {found}")]
+    InvalidPhaseSpaceCount { expected: usize, found: usize },
+    #[error("(I + KC) is singular at s = {0}")]
+    SingularKMatrix(f64),
+    #[error("invalid interpolation tolerance {0:e}: it must be finite and positive")]
+    InvalidTolerance(f64),
+    #[error("interpolating the K-matrix to a tolerance of {tolerance:e} needs more than {max_points} grid points")]
+    GridTooLarge { tolerance: f64, max_points: usize },
+    #[error("LS coupling (2l = {two_l}, 2s = {two_s}) is forbidden for 2j = {two_j} with parity {parity}")]
+    ForbiddenCoupling {
+        two_j: usize,
+        parity: i32,
+        two_l: usize,
+        two_s: usize,
+    },
+    #[error("unknown polarization orientation \"{0}\"")]
+    ParseOrientation(String),
+    #[error("unable to read polarization file \"{path}\": {reason}")]
+    PolarizationFile { path: String, reason: String },
+    #[error("no polarization is known for run {0}")]
+    UnknownRun(u32),
+    #[error("{found} run numbers were given, but the dataset needs {expected}")]
+    MissingRuns { found: usize, expected: usize },
+    #[error("{found} beam helicities were given, but the dataset needs {expected}")]
+    MissingHelicities { found: usize, expected: usize },
+    #[error("the acceptance matrix is singular, so the moments cannot be corrected")]
+    SingularAcceptance,
+    #[error("expected {expected} parameters, got {found}")]
+    InvalidParameterCount { expected: usize, found: usize },
+    #[error("\"{0}\" is not an SDME of the requested spin")]
+    UnknownSDME(String),
+    #[error("{0} is required to transform the other SDMEs")]
+    MissingSDME(String),
+    #[error("the {0} frame is not tied to the production plane, so SDMEs cannot be rotated into or out of it")]
+    UnsupportedFrame(String),
+    #[error("s = {s} and t = {t} are outside the physical region")]
+    UnphysicalKinematics { s: f64, t: f64 },
+    #[error("daughter indices {0:?} must be distinct")]
+    DuplicateDaughterIndices([usize; 3]),
+    #[error("daughter index {index} is out of range for an event with {daughters} daughters")]
+    DaughterIndexOutOfRange { index: usize, daughters: usize },
+    #[error(
+        "unable to parse \"{0}\" as a Dalitz term, expected a label like \"x2y1\" or \"r3sin3\""
+    )]
+    ParseDalitzTerm(String),
+    #[error(
+        "the Dalitz term \"{0}\" is constant or vanishes, so it cannot have its own coefficient"
+    )]
+    TrivialDalitzTerm(String),
+    #[error("the Dalitz term \"{0}\" is given more than once")]
+    DuplicateDalitzTerm(String),
+}
+
+impl From<GluexError> for NodeError {
+    fn from(err: GluexError) -> Self {
+        NodeError::EvaluationError(err.to_string())
+    }
+}
+
+impl From<GluexError> for PyErr {
+    fn from(err: GluexError) -> Self {
+        PyValueError::new_err(err.to_string())
+    }
+}
@@ -4,9 +4,13 @@ use num_complex::ComplexFloat;
 use pyo3::prelude::*;
 use rayon::prelude::*;
 use rustitude_core::prelude::*;
-use sphrs::{ComplexSH, SHEval};
+use sphrs::{ComplexSH, Coordinates, SHEval};
 
-use crate::utils::{Frame, Reflectivity, Wave};
+use crate::{
+    errors::GluexError,
+    polarization::{CircularPolarization, Polarization, PyCircularPolarization, PyPolarization},
+    utils::{extract_wave, sqrt_abs_gradient, Frame, Gradient, PyGradient, Reflectivity, Wave},
+};
 
 pub struct Ylm {
     wave: Wave,
@@ -43,10 +47,26 @@ impl Node for Ylm {
     }
 }
 
+// The decay angles of the first two daughters together with Φ and P_γ, as used by `Zlm` and the
+// moment extraction.
+pub fn zlm_angles(
+    frame: &Frame,
+    polarization: &Polarization,
+    event: &Event,
+) -> Result<(Coordinates<f64>, f64, f64), GluexError> {
+    let resonance = event.daughter_p4s[0] + event.daughter_p4s[1];
+    let daughter_res_vec = event.daughter_p4s[0].boost_along(&resonance).momentum();
+    let (_, y, _, p) = frame.coordinates(&resonance, &daughter_res_vec, event);
+    let (big_phi, pgamma) = polarization.big_phi(&y, event)?;
+    Ok((p, big_phi, pgamma))
+}
+
 pub struct Zlm {
     wave: Wave,
     reflectivity: Reflectivity,
     frame: Frame,
+    polarization: Polarization,
+    circular: Option<CircularPolarization>,
     data: Vec<Complex64>,
 }
 impl Zlm {
@@ -55,38 +75,70 @@ impl Zlm {
             wave,
             reflectivity,
             frame,
+            polarization: Polarization::default(),
+            circular: None,
             data: Vec::default(),
         }
     }
+
+    pub fn with_polarization(mut self, polarization: Polarization) -> Self {
+        self.polarization = polarization;
+        self
+    }
+
+    // A circularly polarized component couples the real and imaginary parts of a reflectivity.
+    // The photon density matrix over those two parts is diagonalized instead, so the real and
+    // imaginary parts of the result (taken downstream, as in the linear case) are its
+    // eigenchannels.
+    pub fn with_circular_polarization(mut self, circular: CircularPolarization) -> Self {
+        self.circular = Some(circular);
+        self
+    }
 }
+
+// √(1 ± P_γ) Z for linear polarization
+fn linear_zlm(zlm: Complex64, reflectivity: Reflectivity, pgamma: f64) -> Complex64 {
+    match reflectivity {
+        Reflectivity::Positive => (1.0 + pgamma).sqrt() * zlm,
+        Reflectivity::Negative => (1.0 - pgamma).sqrt() * zlm,
+    }
+}
+
+// √(1 ± r) Z e^{-iα/2} with r = |(P_γ, P_circ)| and α = atan2(P_circ, P_γ), which reduces to
+// `linear_zlm` when P_circ = 0.
+fn circular_zlm(zlm: Complex64, reflectivity: Reflectivity, pgamma: f64, pcirc: f64) -> Complex64 {
+    let r = pgamma.hypot(pcirc);
+    let rotation = Complex64::cis(-0.5 * pcirc.atan2(pgamma));
+    match reflectivity {
+        Reflectivity::Positive => (1.0 + r).sqrt() * zlm * rotation,
+        Reflectivity::Negative => (1.0 - r).sqrt() * zlm * rotation,
+    }
+}
+
 impl Node for Zlm {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.polarization.check_dataset(dataset)?;
+        if let Some(circular) = &self.circular {
+            circular.check_dataset(dataset)?;
+        }
         self.data = dataset
             .events
             .read()
             .par_iter()
             .map(|event| {
-                let resonance = event.daughter_p4s[0] + event.daughter_p4s[1];
-                let daughter_res_vec = event.daughter_p4s[0].boost_along(&resonance).momentum();
-                let (_, y, _, p) = self.frame.coordinates(&resonance, &daughter_res_vec, event);
+                let (p, big_phi, pgamma) = zlm_angles(&self.frame, &self.polarization, event)?;
                 let ylm = ComplexSH::Spherical.eval(self.wave.l(), self.wave.m(), &p);
-                let big_phi = y.dot(&event.eps).atan2(
-                    event
-                        .beam_p4
-                        .momentum()
-                        .normalize()
-                        .dot(&event.eps.cross(&y)),
-                );
-                let pgamma = event.eps.norm();
 
                 let phase = Complex64::cis(-big_phi);
                 let zlm = ylm * phase;
-                match self.reflectivity {
-                    Reflectivity::Positive => (1.0 + pgamma).sqrt() * zlm,
-                    Reflectivity::Negative => (1.0 - pgamma).sqrt() * zlm,
-                }
+                Ok(match &self.circular {
+                    Some(circular) => {
+                        circular_zlm(zlm, self.reflectivity, pgamma, circular.value(event)?)
+                    }
+                    None => linear_zlm(zlm, self.reflectivity, pgamma),
+                })
             })
-            .collect();
+            .collect::<Result<Vec<Complex64>, GluexError>>()?;
         Ok(())
     }
     fn calculate(&self, _parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
@@ -97,6 +149,7 @@ impl Node for Zlm {
 pub struct OnePS {
     reflectivity: Reflectivity,
     frame: Frame,
+    polarization: Polarization,
     data: Vec<Complex64>,
 }
 impl OnePS {
@@ -104,12 +157,19 @@ impl OnePS {
         Self {
             reflectivity,
             frame,
+            polarization: Polarization::default(),
             data: Vec::default(),
         }
     }
+
+    pub fn with_polarization(mut self, polarization: Polarization) -> Self {
+        self.polarization = polarization;
+        self
+    }
 }
 impl Node for OnePS {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.polarization.check_dataset(dataset)?;
         self.data = dataset
             .events
             .read()
@@ -118,22 +178,14 @@ impl Node for OnePS {
                 let resonance = event.daughter_p4s[0] + event.daughter_p4s[1];
                 let daughter_res_vec = event.daughter_p4s[0].boost_along(&resonance).momentum();
                 let (_, y, _, _) = self.frame.coordinates(&resonance, &daughter_res_vec, event);
-                let pol_angle = event.eps[0].acos();
-                let big_phi = y.dot(&event.eps).atan2(
-                    event
-                        .beam_p4
-                        .momentum()
-                        .normalize()
-                        .dot(&event.eps.cross(&y)),
-                );
-                let pgamma = event.eps.norm();
-                let phase = Complex64::cis(-(pol_angle + big_phi));
-                match self.reflectivity {
+                let (big_phi, pgamma) = self.polarization.big_phi(&y, event)?;
+                let phase = Complex64::cis(-big_phi);
+                Ok(match self.reflectivity {
                     Reflectivity::Positive => (1.0 + pgamma).sqrt() * phase,
                     Reflectivity::Negative => (1.0 - pgamma).sqrt() * phase,
-                }
+                })
             })
-            .collect();
+            .collect::<Result<Vec<Complex64>, GluexError>>()?;
         Ok(())
     }
 
@@ -197,64 +249,194 @@ impl Node for TwoPS {
     }
 }
 
+// Single pseudoscalar photoproduction, |A|² = 1 - P_γ Σ cos(2Φ), where Φ is the angle between the
+// polarization and the production plane (spanned by the beam and the recoil).
+pub struct BeamAsymmetry {
+    polarization: Polarization,
+    data: Vec<f64>,
+}
+impl BeamAsymmetry {
+    pub fn new(polarization: Polarization) -> Self {
+        Self {
+            polarization,
+            data: Vec::default(),
+        }
+    }
+}
+impl Node for BeamAsymmetry {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.polarization.check_dataset(dataset)?;
+        self.data = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let y = event
+                    .beam_p4
+                    .momentum()
+                    .cross(&(-event.recoil_p4.momentum()))
+                    .normalize();
+                let (big_phi, pgamma) = self.polarization.big_phi(&y, event)?;
+                Ok(pgamma * f64::cos(2.0 * big_phi))
+            })
+            .collect::<Result<Vec<f64>, GluexError>>()?;
+        Ok(())
+    }
+
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        Ok(f64::sqrt(f64::abs(1.0 - parameters[0] * self.data[event.index])).into())
+    }
+
+    fn parameters(&self) -> Vec<String> {
+        vec!["sigma".to_string()]
+    }
+}
+
+impl Gradient for BeamAsymmetry {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let p_cos = self.data[event.index];
+        Ok(sqrt_abs_gradient(1.0 - parameters[0] * p_cos, &[-p_cos]))
+    }
+}
+
 #[pyfunction]
-#[pyo3(name = "Ylm", signature = (name, l, m, frame="helicity"))]
-fn ylm(name: &str, l: usize, m: isize, frame: &str) -> PyAmpOp {
-    Amplitude::new(
+#[pyo3(name = "Ylm", signature = (name, l, m=None, frame="helicity"))]
+fn ylm(name: &str, l: &Bound<'_, PyAny>, m: Option<isize>, frame: &str) -> PyResult<PyAmpOp> {
+    Ok(Amplitude::new(
         name,
         Box::new(Ylm::new(
-            Wave::new(l, m),
-            <Frame as std::str::FromStr>::from_str(frame).unwrap(),
+            extract_wave(l, m)?,
+            <Frame as std::str::FromStr>::from_str(frame)?,
         )),
     )
-    .into()
+    .into())
 }
 
 #[pyfunction]
-#[pyo3(name = "Zlm", signature = (name, l, m, reflectivity="positive", frame="helicity"))]
-fn zlm(name: &str, l: usize, m: isize, reflectivity: &str, frame: &str) -> PyAmpOp {
-    Amplitude::new(
+#[pyo3(name = "Zlm", signature = (name, l, m=None, reflectivity="positive", frame="helicity", polarization=None, circular=None))]
+fn zlm(
+    name: &str,
+    l: &Bound<'_, PyAny>,
+    m: Option<isize>,
+    reflectivity: &str,
+    frame: &str,
+    polarization: Option<PyPolarization>,
+    circular: Option<PyCircularPolarization>,
+) -> PyResult<PyAmpOp> {
+    let mut zlm = Zlm::new(
+        extract_wave(l, m)?,
+        <Reflectivity as std::str::FromStr>::from_str(reflectivity)?,
+        <Frame as std::str::FromStr>::from_str(frame)?,
+    )
+    .with_polarization(polarization.map(|p| p.0).unwrap_or_default());
+    if let Some(circular) = circular {
+        zlm = zlm.with_circular_polarization(circular.0);
+    }
+    Ok(Amplitude::new(name, Box::new(zlm)).into())
+}
+
+#[pyfunction]
+#[pyo3(name = "OnePS", signature = (name, reflectivity="positive", frame="helicity", polarization=None))]
+fn one_ps(
+    name: &str,
+    reflectivity: &str,
+    frame: &str,
+    polarization: Option<PyPolarization>,
+) -> PyResult<PyAmpOp> {
+    Ok(Amplitude::new(
         name,
-        Box::new(Zlm::new(
-            Wave::new(l, m),
-            <Reflectivity as std::str::FromStr>::from_str(reflectivity).unwrap(),
-            <Frame as std::str::FromStr>::from_str(frame).unwrap(),
-        )),
+        Box::new(
+            OnePS::new(
+                <Reflectivity as std::str::FromStr>::from_str(reflectivity)?,
+                <Frame as std::str::FromStr>::from_str(frame)?,
+            )
+            .with_polarization(polarization.map(|p| p.0).unwrap_or_default()),
+        ),
     )
-    .into()
+    .into())
 }
 
 #[pyfunction]
-#[pyo3(name = "OnePS", signature = (name, reflectivity="positive", frame="helicity"))]
-fn one_ps(name: &str, reflectivity: &str, frame: &str) -> PyAmpOp {
-    Amplitude::new(
+#[pyo3(name = "TwoPS", signature = (name, l, m=None, reflectivity="positive", frame="helicity"))]
+fn two_ps(
+    name: &str,
+    l: &Bound<'_, PyAny>,
+    m: Option<isize>,
+    reflectivity: &str,
+    frame: &str,
+) -> PyResult<PyAmpOp> {
+    Ok(Amplitude::new(
         name,
-        Box::new(OnePS::new(
-            <Reflectivity as std::str::FromStr>::from_str(reflectivity).unwrap(),
-            <Frame as std::str::FromStr>::from_str(frame).unwrap(),
+        Box::new(TwoPS::new(
+            extract_wave(l, m)?,
+            <Reflectivity as std::str::FromStr>::from_str(reflectivity)?,
+            <Frame as std::str::FromStr>::from_str(frame)?,
         )),
     )
-    .into()
+    .into())
 }
 
 #[pyfunction]
-#[pyo3(name = "TwoPS", signature = (name, l, m, reflectivity="positive", frame="helicity"))]
-fn two_ps(name: &str, l: usize, m: isize, reflectivity: &str, frame: &str) -> PyAmpOp {
+#[pyo3(name = "BeamAsymmetry", signature = (name, polarization=None))]
+fn beam_asymmetry(name: &str, polarization: Option<PyPolarization>) -> PyAmpOp {
     Amplitude::new(
         name,
-        Box::new(TwoPS::new(
-            Wave::new(l, m),
-            <Reflectivity as std::str::FromStr>::from_str(reflectivity).unwrap(),
-            <Frame as std::str::FromStr>::from_str(frame).unwrap(),
+        Box::new(BeamAsymmetry::new(
+            polarization.map(|p| p.0).unwrap_or_default(),
         )),
     )
     .into()
 }
 
+#[pyfunction]
+#[pyo3(name = "BeamAsymmetryGradient", signature = (polarization=None))]
+fn beam_asymmetry_gradient(polarization: Option<PyPolarization>) -> PyGradient {
+    PyGradient::new(BeamAsymmetry::new(
+        polarization.map(|p| p.0).unwrap_or_default(),
+    ))
+}
+
 pub fn pyo3_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(ylm, m)?)?;
     m.add_function(wrap_pyfunction!(zlm, m)?)?;
     m.add_function(wrap_pyfunction!(one_ps, m)?)?;
     m.add_function(wrap_pyfunction!(two_ps, m)?)?;
+    m.add_function(wrap_pyfunction!(beam_asymmetry, m)?)?;
+    m.add_function(wrap_pyfunction!(beam_asymmetry_gradient, m)?)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circular_zlm_reduces_to_linear() {
+        let zlm = Complex64::new(0.3, -0.7);
+        for reflectivity in [Reflectivity::Positive, Reflectivity::Negative] {
+            for pgamma in [0.0, 0.35, 0.8, 1.0] {
+                assert_eq!(
+                    circular_zlm(zlm, reflectivity, pgamma, 0.0),
+                    linear_zlm(zlm, reflectivity, pgamma)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn circular_zlm_channels_are_orthogonal() {
+        // The real and imaginary parts project onto orthogonal eigenvectors of the photon
+        // density matrix, whose eigenvalues 1 ± r add up to the unpolarized 2.
+        let (pgamma, pcirc) = (0.6, 0.5);
+        let re = circular_zlm(Complex64::from(1.0), Reflectivity::Positive, pgamma, pcirc);
+        let im = circular_zlm(Complex64::i(), Reflectivity::Positive, pgamma, pcirc);
+        assert!((re.re * im.re + re.im * im.im).abs() < 1e-12);
+        let total = [Reflectivity::Positive, Reflectivity::Negative]
+            .iter()
+            .map(|&reflectivity| {
+                circular_zlm(Complex64::from(1.0), reflectivity, pgamma, pcirc).norm_sqr()
+            })
+            .sum::<f64>();
+        assert!((total - 2.0).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,613 @@
+use nalgebra::Vector3;
+use pyo3::{
+    exceptions::{PyKeyError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+use rayon::prelude::*;
+use rustitude_core::prelude::*;
+use sphrs::SHCoordinates;
+
+use crate::{
+    errors::GluexError,
+    utils::{
+        check_projection, clebsch_gordan, doubled, doubled_spin, extract_complex, triangle,
+        wigner_d_matrix, Frame,
+    },
+};
+
+#[derive(Clone, Debug)]
+pub enum Couplings {
+    Helicity(Vec<(isize, isize, Complex64)>),
+    LS(Vec<(usize, usize, Complex64)>),
+}
+
+#[derive(Clone, Debug)]
+pub enum DecayProduct {
+    Stable {
+        indices: Vec<usize>,
+        two_s: usize,
+        parity: i32,
+        two_lambda: isize,
+    },
+    Isobar(Box<Decay>),
+}
+
+impl DecayProduct {
+    pub fn stable(indices: &[usize], two_s: usize, parity: i32, two_lambda: isize) -> Self {
+        Self::Stable {
+            indices: indices.to_vec(),
+            two_s,
+            parity,
+            two_lambda,
+        }
+    }
+
+    pub fn isobar(decay: Decay) -> Self {
+        Self::Isobar(Box::new(decay))
+    }
+
+    pub fn two_j(&self) -> usize {
+        match self {
+            Self::Stable { two_s, .. } => *two_s,
+            Self::Isobar(decay) => decay.two_j(),
+        }
+    }
+
+    pub fn parity(&self) -> i32 {
+        match self {
+            Self::Stable { parity, .. } => *parity,
+            Self::Isobar(decay) => decay.parity(),
+        }
+    }
+
+    pub fn indices(&self) -> Vec<usize> {
+        match self {
+            Self::Stable { indices, .. } => indices.clone(),
+            Self::Isobar(decay) => decay.indices(),
+        }
+    }
+
+    fn resolve(&self) -> Result<Leg, GluexError> {
+        match self {
+            Self::Stable {
+                indices,
+                two_s,
+                two_lambda,
+                ..
+            } => {
+                check_projection(*two_s, *two_lambda)?;
+                Ok(Leg::Stable {
+                    indices: indices.clone(),
+                    two_lambda: *two_lambda,
+                })
+            }
+            Self::Isobar(decay) => Ok(Leg::Isobar(decay.resolve()?)),
+        }
+    }
+}
+
+// A three-body decay is analysed through the normal to its decay plane, as for ω → 3π.
+#[derive(Clone, Debug)]
+pub enum Decay {
+    TwoBody {
+        two_j: usize,
+        parity: i32,
+        products: Box<[DecayProduct; 2]>,
+        couplings: Couplings,
+    },
+    ThreeBody {
+        two_j: usize,
+        parity: i32,
+        indices: [usize; 3],
+    },
+}
+
+impl Decay {
+    pub fn two_body(
+        two_j: usize,
+        parity: i32,
+        products: [DecayProduct; 2],
+        couplings: Couplings,
+    ) -> Self {
+        Self::TwoBody {
+            two_j,
+            parity,
+            products: Box::new(products),
+            couplings,
+        }
+    }
+
+    pub fn three_body(two_j: usize, parity: i32, indices: [usize; 3]) -> Self {
+        Self::ThreeBody {
+            two_j,
+            parity,
+            indices,
+        }
+    }
+
+    pub fn two_j(&self) -> usize {
+        match self {
+            Self::TwoBody { two_j, .. } | Self::ThreeBody { two_j, .. } => *two_j,
+        }
+    }
+
+    pub fn parity(&self) -> i32 {
+        match self {
+            Self::TwoBody { parity, .. } | Self::ThreeBody { parity, .. } => *parity,
+        }
+    }
+
+    pub fn indices(&self) -> Vec<usize> {
+        match self {
+            Self::TwoBody { products, .. } => {
+                let mut indices = products[0].indices();
+                indices.extend(products[1].indices());
+                indices
+            }
+            Self::ThreeBody { indices, .. } => indices.to_vec(),
+        }
+    }
+
+    pub fn helicity_couplings(&self) -> Result<Vec<(isize, isize, Complex64)>, GluexError> {
+        let Self::TwoBody {
+            two_j,
+            parity,
+            products,
+            couplings,
+        } = self
+        else {
+            return Ok(vec![(0, 0, Complex64::new(1.0, 0.0))]);
+        };
+        let (two_j, parity) = (*two_j, *parity);
+        let (two_s1, two_s2) = (products[0].two_j(), products[1].two_j());
+        match couplings {
+            Couplings::Helicity(couplings) => {
+                for &(two_lambda1, two_lambda2, _) in couplings {
+                    check_projection(two_s1, two_lambda1)?;
+                    check_projection(two_s2, two_lambda2)?;
+                    check_projection(two_j, two_lambda1 - two_lambda2)?;
+                }
+                Ok(couplings.clone())
+            }
+            Couplings::LS(couplings) => {
+                let intrinsic = products[0].parity() * products[1].parity();
+                for &(two_l, two_s, _) in couplings {
+                    let orbital = if two_l % 4 == 0 { 1 } else { -1 };
+                    if two_l & 1 == 1
+                        || parity != intrinsic * orbital
+                        || !triangle(two_l, two_s, two_j)
+                        || !triangle(two_s1, two_s2, two_s)
+                    {
+                        return Err(GluexError::ForbiddenCoupling {
+                            two_j,
+                            parity,
+                            two_l,
+                            two_s,
+                        });
+                    }
+                }
+                let mut helicity_couplings = Vec::new();
+                for two_lambda1 in (-(two_s1 as isize)..=two_s1 as isize).step_by(2) {
+                    for two_lambda2 in (-(two_s2 as isize)..=two_s2 as isize).step_by(2) {
+                        let two_delta = two_lambda1 - two_lambda2;
+                        if two_delta.unsigned_abs() > two_j {
+                            continue;
+                        }
+                        let mut h = Complex64::default();
+                        for &(two_l, two_s, g) in couplings {
+                            if two_delta.unsigned_abs() > two_s {
+                                continue;
+                            }
+                            h += g
+                                * f64::sqrt((two_l as f64 + 1.0) / (two_j as f64 + 1.0))
+                                * clebsch_gordan(two_l, 0, two_s, two_delta, two_j, two_delta)?
+                                * clebsch_gordan(
+                                    two_s1,
+                                    two_lambda1,
+                                    two_s2,
+                                    -two_lambda2,
+                                    two_s,
+                                    two_delta,
+                                )?;
+                        }
+                        if h.norm() > 0.0 {
+                            helicity_couplings.push((two_lambda1, two_lambda2, h));
+                        }
+                    }
+                }
+                Ok(helicity_couplings)
+            }
+        }
+    }
+
+    fn resolve(&self) -> Result<Vertex, GluexError> {
+        match self {
+            Self::TwoBody {
+                two_j, products, ..
+            } => Ok(Vertex::TwoBody {
+                two_j: *two_j,
+                couplings: self.helicity_couplings()?,
+                legs: Box::new([products[0].resolve()?, products[1].resolve()?]),
+            }),
+            Self::ThreeBody { two_j, indices, .. } => Ok(Vertex::ThreeBody {
+                two_j: *two_j,
+                indices: *indices,
+            }),
+        }
+    }
+}
+
+type Axes = (Vector3<f64>, Vector3<f64>, Vector3<f64>);
+
+enum Leg {
+    Stable {
+        indices: Vec<usize>,
+        two_lambda: isize,
+    },
+    Isobar(Vertex),
+}
+
+impl Leg {
+    fn indices(&self) -> Vec<usize> {
+        match self {
+            Leg::Stable { indices, .. } => indices.clone(),
+            Leg::Isobar(vertex) => vertex.indices(),
+        }
+    }
+
+    fn amplitude(
+        &self,
+        two_lambda: isize,
+        p4s: &[FourMomentum],
+        z_parent: &Vector3<f64>,
+    ) -> Result<Complex64, GluexError> {
+        match self {
+            Leg::Stable {
+                two_lambda: fixed, ..
+            } => Ok(if two_lambda == *fixed {
+                Complex64::new(1.0, 0.0)
+            } else {
+                Complex64::default()
+            }),
+            Leg::Isobar(vertex) => {
+                let isobar: FourMomentum = vertex.indices().iter().map(|&i| &p4s[i]).sum();
+                let z = isobar.momentum().normalize();
+                let y = z_parent.cross(&z).normalize();
+                let x = y.cross(&z);
+                let boosted: Vec<FourMomentum> =
+                    p4s.iter().map(|p4| p4.boost_along(&isobar)).collect();
+                vertex.amplitude(two_lambda, &boosted, &(x, y, z))
+            }
+        }
+    }
+}
+
+enum Vertex {
+    TwoBody {
+        two_j: usize,
+        couplings: Vec<(isize, isize, Complex64)>,
+        legs: Box<[Leg; 2]>,
+    },
+    ThreeBody {
+        two_j: usize,
+        indices: [usize; 3],
+    },
+}
+
+impl Vertex {
+    fn indices(&self) -> Vec<usize> {
+        match self {
+            Vertex::TwoBody { legs, .. } => {
+                let mut indices = legs[0].indices();
+                indices.extend(legs[1].indices());
+                indices
+            }
+            Vertex::ThreeBody { indices, .. } => indices.to_vec(),
+        }
+    }
+
+    fn analyzer(&self, p4s: &[FourMomentum]) -> Vector3<f64> {
+        match self {
+            Vertex::TwoBody { legs, .. } => legs[0]
+                .indices()
+                .iter()
+                .map(|&i| &p4s[i])
+                .sum::<FourMomentum>()
+                .momentum(),
+            Vertex::ThreeBody { indices, .. } => p4s[indices[0]]
+                .momentum()
+                .cross(&p4s[indices[1]].momentum()),
+        }
+    }
+
+    // `p4s` are given in the rest frame of the decaying particle, quantized along `axes`.
+    fn amplitude(
+        &self,
+        two_m: isize,
+        p4s: &[FourMomentum],
+        axes: &Axes,
+    ) -> Result<Complex64, GluexError> {
+        let (x, y, z) = axes;
+        let v = self.analyzer(p4s);
+        let theta = (v.dot(z) / v.norm()).acos();
+        let phi = v.dot(y).atan2(v.dot(x));
+        match self {
+            Vertex::TwoBody {
+                two_j,
+                couplings,
+                legs,
+            } => couplings.iter().try_fold(
+                Complex64::default(),
+                |acc, &(two_lambda1, two_lambda2, h)| {
+                    let a1 = legs[0].amplitude(two_lambda1, p4s, z)?;
+                    let a2 = legs[1].amplitude(two_lambda2, p4s, z)?;
+                    if a1.norm() == 0.0 || a2.norm() == 0.0 {
+                        return Ok(acc);
+                    }
+                    Ok(acc
+                        + h * wigner_d_matrix(
+                            phi,
+                            theta,
+                            0.0,
+                            *two_j,
+                            two_m,
+                            two_lambda1 - two_lambda2,
+                        )?
+                        .conj()
+                            * a1
+                            * a2)
+                },
+            ),
+            Vertex::ThreeBody { two_j, .. } => {
+                Ok(wigner_d_matrix(phi, theta, 0.0, *two_j, two_m, 0)?.conj())
+            }
+        }
+    }
+}
+
+pub struct DecayChain {
+    vertex: Vertex,
+    two_m: isize,
+    frame: Frame,
+    data: Vec<Complex64>,
+}
+
+impl DecayChain {
+    pub fn new(decay: &Decay, two_m: isize, frame: Frame) -> Result<Self, GluexError> {
+        check_projection(decay.two_j(), two_m)?;
+        Ok(Self {
+            vertex: decay.resolve()?,
+            two_m,
+            frame,
+            data: Vec::default(),
+        })
+    }
+}
+
+impl Node for DecayChain {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.data = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let resonance: FourMomentum = self
+                    .vertex
+                    .indices()
+                    .iter()
+                    .map(|&i| &event.daughter_p4s[i])
+                    .sum();
+                let boosted: Vec<FourMomentum> = event
+                    .daughter_p4s
+                    .iter()
+                    .map(|p4| p4.boost_along(&resonance))
+                    .collect();
+                let (x, y, z, _) =
+                    self.frame
+                        .coordinates(&resonance, &self.vertex.analyzer(&boosted), event);
+                self.vertex.amplitude(self.two_m, &boosted, &(x, y, z))
+            })
+            .collect::<Result<Vec<Complex64>, GluexError>>()?;
+        Ok(())
+    }
+
+    fn calculate(&self, _parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        Ok(self.data[event.index])
+    }
+}
+
+pub struct TwoBodyHelicity {
+    two_j: usize,
+    two_m: isize,
+    two_lambda1: isize,
+    two_lambda2: isize,
+    p1_indices: Vec<usize>,
+    p2_indices: Vec<usize>,
+    frame: Frame,
+    data: Vec<Complex64>,
+}
+
+impl TwoBodyHelicity {
+    pub fn new(
+        two_j: usize,
+        two_m: isize,
+        two_lambda1: isize,
+        two_lambda2: isize,
+        p1_indices: &[usize],
+        p2_indices: &[usize],
+        frame: Frame,
+    ) -> Result<Self, GluexError> {
+        check_projection(two_j, two_m)?;
+        check_projection(two_j, two_lambda1 - two_lambda2)?;
+        Ok(Self {
+            two_j,
+            two_m,
+            two_lambda1,
+            two_lambda2,
+            p1_indices: p1_indices.into(),
+            p2_indices: p2_indices.into(),
+            frame,
+            data: Vec::default(),
+        })
+    }
+}
+
+impl Node for TwoBodyHelicity {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.data = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let p1: FourMomentum = self
+                    .p1_indices
+                    .iter()
+                    .map(|i| &event.daughter_p4s[*i])
+                    .sum();
+                let p2: FourMomentum = self
+                    .p2_indices
+                    .iter()
+                    .map(|i| &event.daughter_p4s[*i])
+                    .sum();
+                let resonance = p1 + p2;
+                let daughter_res_vec = p1.boost_along(&resonance).momentum();
+                let (_, _, _, p) = self.frame.coordinates(&resonance, &daughter_res_vec, event);
+                Ok(wigner_d_matrix(
+                    p.phi(),
+                    p.theta(),
+                    0.0,
+                    self.two_j,
+                    self.two_m,
+                    self.two_lambda1 - self.two_lambda2,
+                )?
+                .conj())
+            })
+            .collect::<Result<Vec<Complex64>, GluexError>>()?;
+        Ok(())
+    }
+
+    fn calculate(&self, _parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        Ok(self.data[event.index])
+    }
+}
+
+fn required<'py>(dict: &Bound<'py, PyDict>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+    dict.get_item(key)?
+        .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+}
+
+fn extract_couplings(dict: &Bound<'_, PyDict>) -> PyResult<Couplings> {
+    if let Some(ls) = dict.get_item("ls")? {
+        Ok(Couplings::LS(
+            ls.extract::<Vec<(f64, f64, Bound<'_, PyAny>)>>()?
+                .iter()
+                .map(|(l, s, g)| Ok((doubled_spin(*l)?, doubled_spin(*s)?, extract_complex(g)?)))
+                .collect::<PyResult<_>>()?,
+        ))
+    } else if let Some(helicity) = dict.get_item("helicity")? {
+        Ok(Couplings::Helicity(
+            helicity
+                .extract::<Vec<(f64, f64, Bound<'_, PyAny>)>>()?
+                .iter()
+                .map(|(lambda1, lambda2, h)| {
+                    Ok((doubled(*lambda1)?, doubled(*lambda2)?, extract_complex(h)?))
+                })
+                .collect::<PyResult<_>>()?,
+        ))
+    } else {
+        Err(PyKeyError::new_err("ls"))
+    }
+}
+
+fn extract_product(product: &Bound<'_, PyAny>) -> PyResult<DecayProduct> {
+    let dict = product.downcast::<PyDict>()?;
+    match dict.get_item("indices")? {
+        Some(indices) => Ok(DecayProduct::stable(
+            &indices.extract::<Vec<usize>>()?,
+            match dict.get_item("spin")? {
+                Some(spin) => doubled_spin(spin.extract()?)?,
+                None => 0,
+            },
+            match dict.get_item("parity")? {
+                Some(parity) => parity.extract()?,
+                None => -1,
+            },
+            match dict.get_item("helicity")? {
+                Some(helicity) => doubled(helicity.extract()?)?,
+                None => 0,
+            },
+        )),
+        None => Ok(DecayProduct::isobar(extract_decay(product)?)),
+    }
+}
+
+fn extract_decay(decay: &Bound<'_, PyAny>) -> PyResult<Decay> {
+    let dict = decay.downcast::<PyDict>()?;
+    let two_j = doubled_spin(required(dict, "spin")?.extract()?)?;
+    let parity = required(dict, "parity")?.extract()?;
+    if let Some(indices) = dict.get_item("three_body")? {
+        return Ok(Decay::three_body(two_j, parity, indices.extract()?));
+    }
+    let products = required(dict, "products")?.extract::<Vec<Bound<'_, PyAny>>>()?;
+    let [p1, p2] = products.as_slice() else {
+        return Err(PyValueError::new_err(format!(
+            "expected 2 decay products, got {}",
+            products.len()
+        )));
+    };
+    Ok(Decay::two_body(
+        two_j,
+        parity,
+        [extract_product(p1)?, extract_product(p2)?],
+        extract_couplings(dict)?,
+    ))
+}
+
+#[pyfunction]
+#[pyo3(name = "DecayChain", signature = (name, decay, m=0.0, frame="helicity"))]
+fn decay_chain(name: &str, decay: &Bound<'_, PyAny>, m: f64, frame: &str) -> PyResult<PyAmpOp> {
+    Ok(Amplitude::new(
+        name,
+        Box::new(DecayChain::new(
+            &extract_decay(decay)?,
+            doubled(m)?,
+            <Frame as std::str::FromStr>::from_str(frame)?,
+        )?),
+    )
+    .into())
+}
+
+#[pyfunction]
+#[pyo3(name = "TwoBodyHelicity", signature = (name, j, m, lambda1=0.0, lambda2=0.0, p1_indices=vec![0], p2_indices=vec![1], frame="helicity"))]
+#[allow(clippy::too_many_arguments)]
+fn two_body_helicity(
+    name: &str,
+    j: f64,
+    m: f64,
+    lambda1: f64,
+    lambda2: f64,
+    p1_indices: Vec<usize>,
+    p2_indices: Vec<usize>,
+    frame: &str,
+) -> PyResult<PyAmpOp> {
+    Ok(Amplitude::new(
+        name,
+        Box::new(TwoBodyHelicity::new(
+            doubled_spin(j)?,
+            doubled(m)?,
+            doubled(lambda1)?,
+            doubled(lambda2)?,
+            &p1_indices,
+            &p2_indices,
+            <Frame as std::str::FromStr>::from_str(frame)?,
+        )?),
+    )
+    .into())
+}
+
+pub fn pyo3_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(decay_chain, m)?)?;
+    m.add_function(wrap_pyfunction!(two_body_helicity, m)?)?;
+    Ok(())
+}
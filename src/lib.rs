@@ -1,5 +1,9 @@
 pub mod dalitz;
+pub mod errors;
 pub mod harmonics;
+pub mod helicity;
+pub mod moments;
+pub mod polarization;
 pub mod resonances;
 pub mod sdmes;
 pub mod utils;
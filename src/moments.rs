@@ -0,0 +1,376 @@
+use std::{collections::HashMap, f64::consts::PI, str::FromStr};
+
+use nalgebra::{DMatrix, DVector};
+use pyo3::{prelude::*, types::PyDict};
+use rayon::prelude::*;
+use rustitude_core::prelude::*;
+use sphrs::SHCoordinates;
+
+use crate::{
+    errors::GluexError,
+    harmonics::zlm_angles,
+    polarization::{Polarization, PyPolarization},
+    utils::{clebsch_gordan, extract_complex, wigner_d_matrix, Frame, Reflectivity, Wave},
+};
+
+// Moments follow the convention
+//   dN/dΩ(dΦ/2π) = I⁰(Ω) - P_γ cos(2Φ) I¹(Ω) - P_γ sin(2Φ) I²(Ω),
+//   I⁰,¹(Ω) = Σ (2L+1)/4π H⁰,¹(LM) Re[D^L_{M0}(φ, θ, 0)],
+//   I²(Ω) = Σ (2L+1)/4π H²(LM) Im[D^L_{M0}(φ, θ, 0)],
+// summed over -L ≤ M ≤ L, so H⁰(00) is the (weighted) yield. Only M ≥ 0 is stored since the
+// negative projections follow by symmetry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MomentIndex {
+    pub alpha: usize,
+    pub l: usize,
+    pub m: usize,
+}
+
+#[derive(Clone, Debug)]
+pub struct Moments {
+    pub indices: Vec<MomentIndex>,
+    pub values: DVector<f64>,
+    pub covariance: DMatrix<f64>,
+}
+
+impl Moments {
+    pub fn get(&self, alpha: usize, l: usize, m: usize) -> Option<(f64, f64)> {
+        let k = self
+            .indices
+            .iter()
+            .position(|index| *index == MomentIndex { alpha, l, m })?;
+        Some((self.values[k], self.covariance[(k, k)].sqrt()))
+    }
+}
+
+fn moment_indices(l_max: usize, polarized: bool) -> Vec<MomentIndex> {
+    let alphas = if polarized { 0..3 } else { 0..1 };
+    alphas
+        .flat_map(|alpha| {
+            (0..=l_max).flat_map(move |l| {
+                (0..=l)
+                    .filter(move |&m| alpha != 2 || m != 0)
+                    .map(move |m| MomentIndex { alpha, l, m })
+            })
+        })
+        .collect()
+}
+
+// Returns the per-event estimator of each moment along with the basis function multiplying it
+// in the intensity, and whether the event is polarized at all.
+fn event_terms(
+    indices: &[MomentIndex],
+    frame: &Frame,
+    polarization: &Polarization,
+    event: &Event,
+) -> Result<(DVector<f64>, DVector<f64>, bool), GluexError> {
+    let (p, big_phi, pgamma) = zlm_angles(frame, polarization, event)?;
+    let mut estimators = DVector::zeros(indices.len());
+    let mut basis = DVector::zeros(indices.len());
+    for (k, index) in indices.iter().enumerate() {
+        let d = wigner_d_matrix(
+            p.phi(),
+            p.theta(),
+            0.0,
+            2 * index.l,
+            2 * index.m as isize,
+            0,
+        )?;
+        let norm = (2.0 * index.l as f64 + 1.0) / (4.0 * PI) * if index.m > 0 { 2.0 } else { 1.0 };
+        match index.alpha {
+            0 => {
+                estimators[k] = d.re;
+                basis[k] = norm * d.re;
+            }
+            _ if pgamma == 0.0 => {}
+            1 => {
+                estimators[k] = -2.0 * d.re * f64::cos(2.0 * big_phi) / pgamma;
+                basis[k] = -pgamma * f64::cos(2.0 * big_phi) * norm * d.re;
+            }
+            _ => {
+                estimators[k] = -2.0 * d.im * f64::sin(2.0 * big_phi) / pgamma;
+                basis[k] = -pgamma * f64::sin(2.0 * big_phi) * norm * d.im;
+            }
+        }
+    }
+    Ok((estimators, basis, pgamma != 0.0))
+}
+
+// Unpolarized events carry no information on H¹ and H², so those moments are estimated from the
+// polarized events alone and scaled up to the full (weighted) yield.
+fn polarized_scale(indices: &[MomentIndex], total: f64, polarized: f64) -> DVector<f64> {
+    DVector::from_iterator(
+        indices.len(),
+        indices.iter().map(|index| {
+            if index.alpha == 0 || polarized == 0.0 {
+                1.0
+            } else {
+                total / polarized
+            }
+        }),
+    )
+}
+
+pub fn moments(
+    dataset: &Dataset,
+    l_max: usize,
+    frame: &Frame,
+    polarization: &Polarization,
+) -> Result<Moments, GluexError> {
+    polarization.check_dataset(dataset)?;
+    let indices = moment_indices(l_max, !matches!(polarization, Polarization::Unpolarized));
+    let n = indices.len();
+    let (values, covariance, total, polarized) = dataset
+        .events
+        .read()
+        .par_iter()
+        .map(|event| {
+            let (estimators, _, is_polarized) = event_terms(&indices, frame, polarization, event)?;
+            Ok((
+                estimators.scale(event.weight),
+                (&estimators * estimators.transpose()).scale(event.weight.powi(2)),
+                event.weight,
+                if is_polarized { event.weight } else { 0.0 },
+            ))
+        })
+        .try_reduce(
+            || (DVector::zeros(n), DMatrix::zeros(n, n), 0.0, 0.0),
+            |a, b| Ok((a.0 + b.0, a.1 + b.1, a.2 + b.2, a.3 + b.3)),
+        )?;
+    let scale = polarized_scale(&indices, total, polarized);
+    Ok(Moments {
+        values: values.component_mul(&scale),
+        covariance: covariance.component_mul(&(&scale * scale.transpose())),
+        indices,
+    })
+}
+
+// Corrects the moments of `data` for acceptance using phase-space Monte Carlo generated flat in
+// (Ω, Φ), where `accepted` holds the reconstructed events and `generated` the full sample. The
+// acceptance matrix mixes the α terms through P_γ, so `mc_polarization` must describe the Monte
+// Carlo with the same polarization model as the data.
+pub fn acceptance_corrected_moments(
+    data: &Dataset,
+    accepted: &Dataset,
+    generated: &Dataset,
+    l_max: usize,
+    frame: &Frame,
+    polarization: &Polarization,
+    mc_polarization: &Polarization,
+) -> Result<Moments, GluexError> {
+    let measured = moments(data, l_max, frame, polarization)?;
+    mc_polarization.check_dataset(accepted)?;
+    let n = measured.indices.len();
+    let n_generated: f64 = generated.events.read().par_iter().map(|e| e.weight).sum();
+    let (acceptance, total, polarized) = accepted
+        .events
+        .read()
+        .par_iter()
+        .map(|event| {
+            let (estimators, basis, is_polarized) =
+                event_terms(&measured.indices, frame, mc_polarization, event)?;
+            Ok((
+                (&estimators * basis.transpose()).scale(event.weight),
+                event.weight,
+                if is_polarized { event.weight } else { 0.0 },
+            ))
+        })
+        .try_reduce(
+            || (DMatrix::zeros(n, n), 0.0, 0.0),
+            |a, b| Ok((a.0 + b.0, a.1 + b.1, a.2 + b.2)),
+        )?;
+    let acceptance = DMatrix::from_diagonal(&polarized_scale(&measured.indices, total, polarized))
+        * acceptance.scale(4.0 * PI / n_generated);
+    let inverse = acceptance
+        .try_inverse()
+        .ok_or(GluexError::SingularAcceptance)?;
+    Ok(Moments {
+        values: &inverse * &measured.values,
+        covariance: &inverse * &measured.covariance * inverse.transpose(),
+        indices: measured.indices,
+    })
+}
+
+// ∫ Y_{l1 m1} Y_{l2 m2} Y*_{LM} dΩ
+fn gaunt(
+    l1: usize,
+    m1: isize,
+    l2: usize,
+    m2: isize,
+    l: usize,
+    m: isize,
+) -> Result<f64, GluexError> {
+    if m1 + m2 != m {
+        return Ok(0.0);
+    }
+    Ok(f64::sqrt(
+        (2.0 * l1 as f64 + 1.0) * (2.0 * l2 as f64 + 1.0) / (4.0 * PI * (2.0 * l as f64 + 1.0)),
+    ) * clebsch_gordan(2 * l1, 0, 2 * l2, 0, 2 * l, 0)?
+        * clebsch_gordan(2 * l1, 2 * m1, 2 * l2, 2 * m2, 2 * l, 2 * m)?)
+}
+
+fn phase(m: isize) -> f64 {
+    if m % 2 == 0 {
+        1.0
+    } else {
+        -1.0
+    }
+}
+
+// Predicts the moments of a fit built from `Zlm` amplitudes, where the intensity is
+//   Σ_ε (1 + εP_γ)|Σ [ℓm]^ε Re(Z^m_ℓ)|² + (1 - εP_γ)|Σ [ℓm]^ε Im(Z^m_ℓ)|²,
+// so that, with X_ε = Σ [ℓm]^ε [ℓ'm']^ε* Y^m_ℓ Y^m'_ℓ',
+//   I⁰ = Σ_ε (|Σ [ℓm]^ε Y^m_ℓ|² + |Σ [ℓm]^ε Y^m*_ℓ|²) / 2,  I¹ = -Σ_ε ε Re(X_ε),  I² = -Σ_ε ε Im(X_ε).
+pub fn project_moments(
+    amplitudes: &HashMap<(Wave, Reflectivity), Complex64>,
+    l_max: usize,
+) -> Result<Moments, GluexError> {
+    let indices = moment_indices(l_max, true);
+    let values = indices
+        .iter()
+        .map(|index| {
+            let (l, m) = (index.l, index.m as isize);
+            let mut h = Complex64::default();
+            for ((wave_i, reflectivity_i), c_i) in amplitudes {
+                for ((wave_j, reflectivity_j), c_j) in amplitudes {
+                    if reflectivity_i != reflectivity_j {
+                        continue;
+                    }
+                    let eps = *reflectivity_i as isize as f64;
+                    let (l_i, m_i) = (wave_i.l() as usize, wave_i.m() as isize);
+                    let (l_j, m_j) = (wave_j.l() as usize, wave_j.m() as isize);
+                    let cc = c_i * c_j.conj();
+                    h += match index.alpha {
+                        0 => {
+                            0.5 * cc
+                                * (phase(m_j) * gaunt(l_i, m_i, l_j, -m_j, l, m)?
+                                    + phase(m_i) * gaunt(l_j, m_j, l_i, -m_i, l, m)?)
+                        }
+                        _ => {
+                            let x = cc * gaunt(l_i, m_i, l_j, m_j, l, m)?;
+                            let x_conj =
+                                cc.conj() * phase(m_i + m_j) * gaunt(l_i, -m_i, l_j, -m_j, l, m)?;
+                            if index.alpha == 1 {
+                                -eps * (x + x_conj) / 2.0
+                            } else {
+                                -eps * (x - x_conj) / Complex64::new(0.0, 2.0)
+                            }
+                        }
+                    };
+                }
+            }
+            let h = h * f64::sqrt(4.0 * PI / (2.0 * l as f64 + 1.0));
+            Ok(if index.alpha == 2 { h.im } else { h.re })
+        })
+        .collect::<Result<Vec<f64>, GluexError>>()?;
+    let n = indices.len();
+    Ok(Moments {
+        indices,
+        values: DVector::from_vec(values),
+        covariance: DMatrix::zeros(n, n),
+    })
+}
+
+type PyMoments = (Vec<(usize, usize, usize)>, Vec<f64>, Vec<Vec<f64>>);
+
+// Python sees moments as (indices, values, covariance), with indices given as (alpha, l, m).
+fn py_moments_tuple(moments: Moments) -> PyMoments {
+    (
+        moments
+            .indices
+            .iter()
+            .map(|index| (index.alpha, index.l, index.m))
+            .collect(),
+        moments.values.iter().copied().collect(),
+        moments
+            .covariance
+            .row_iter()
+            .map(|row| row.iter().copied().collect())
+            .collect(),
+    )
+}
+
+#[pyfunction(name = "moments")]
+#[pyo3(signature = (dataset, l_max, frame="helicity", polarization=None))]
+fn py_moments(
+    dataset: Dataset,
+    l_max: usize,
+    frame: &str,
+    polarization: Option<PyPolarization>,
+) -> PyResult<PyMoments> {
+    Ok(py_moments_tuple(moments(
+        &dataset,
+        l_max,
+        &Frame::from_str(frame)?,
+        &polarization.map(|p| p.0).unwrap_or_default(),
+    )?))
+}
+
+#[pyfunction(name = "acceptance_corrected_moments")]
+#[pyo3(signature = (data, accepted, generated, l_max, frame="helicity", polarization=None, mc_polarization=None))]
+fn py_acceptance_corrected_moments(
+    data: Dataset,
+    accepted: Dataset,
+    generated: Dataset,
+    l_max: usize,
+    frame: &str,
+    polarization: Option<PyPolarization>,
+    mc_polarization: Option<PyPolarization>,
+) -> PyResult<PyMoments> {
+    let polarization = polarization.map(|p| p.0).unwrap_or_default();
+    // the Monte Carlo carries the data's polarization unless told otherwise
+    let mc_polarization = mc_polarization
+        .map(|p| p.0)
+        .unwrap_or_else(|| polarization.clone());
+    Ok(py_moments_tuple(acceptance_corrected_moments(
+        &data,
+        &accepted,
+        &generated,
+        l_max,
+        &Frame::from_str(frame)?,
+        &polarization,
+        &mc_polarization,
+    )?))
+}
+
+#[pyfunction(name = "project_moments")]
+#[pyo3(signature = (amplitudes, l_max=None))]
+fn py_project_moments(
+    amplitudes: &Bound<'_, PyDict>,
+    l_max: Option<usize>,
+) -> PyResult<HashMap<(usize, usize, usize), f64>> {
+    let amplitudes = amplitudes
+        .iter()
+        .map(|(key, value)| {
+            let (wave, reflectivity): (String, String) = key.extract()?;
+            Ok((
+                (
+                    Wave::from_str(&wave)?,
+                    Reflectivity::from_str(&reflectivity)?,
+                ),
+                extract_complex(&value)?,
+            ))
+        })
+        .collect::<PyResult<HashMap<(Wave, Reflectivity), Complex64>>>()?;
+    let l_max = l_max.unwrap_or_else(|| {
+        2 * amplitudes
+            .keys()
+            .map(|(wave, _)| wave.l() as usize)
+            .max()
+            .unwrap_or_default()
+    });
+    let moments = project_moments(&amplitudes, l_max)?;
+    Ok(moments
+        .indices
+        .iter()
+        .zip(moments.values.iter())
+        .map(|(index, value)| ((index.alpha, index.l, index.m), *value))
+        .collect())
+}
+
+pub fn pyo3_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_moments, m)?)?;
+    m.add_function(wrap_pyfunction!(py_acceptance_corrected_moments, m)?)?;
+    m.add_function(wrap_pyfunction!(py_project_moments, m)?)?;
+    Ok(())
+}
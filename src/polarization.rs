@@ -0,0 +1,356 @@
+use std::{collections::HashMap, fs, path::Path, str::FromStr};
+
+use nalgebra::Vector3;
+use pyo3::prelude::*;
+use rustitude_core::prelude::*;
+
+use crate::errors::GluexError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Deg0,
+    Deg45,
+    Deg90,
+    Deg135,
+    Amo,
+}
+
+impl FromStr for Orientation {
+    type Err = GluexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "0" => Ok(Orientation::Deg0),
+            "para_0" => Ok(Orientation::Deg0),
+            "45" => Ok(Orientation::Deg45),
+            "perp_45" => Ok(Orientation::Deg45),
+            "90" => Ok(Orientation::Deg90),
+            "perp_90" => Ok(Orientation::Deg90),
+            "135" => Ok(Orientation::Deg135),
+            "para_135" => Ok(Orientation::Deg135),
+            "amo" => Ok(Orientation::Amo),
+            "amorphous" => Ok(Orientation::Amo),
+            _ => Err(GluexError::ParseOrientation(s.to_string())),
+        }
+    }
+}
+
+impl Orientation {
+    pub fn angle(&self) -> f64 {
+        match self {
+            Orientation::Deg0 | Orientation::Amo => 0.0,
+            Orientation::Deg45 => 45f64.to_radians(),
+            Orientation::Deg90 => 90f64.to_radians(),
+            Orientation::Deg135 => 135f64.to_radians(),
+        }
+    }
+}
+
+// Angles are stored in radians and measured from the lab x-axis; magnitudes are the degree of
+// linear polarization. `Event` keeps the old behaviour of reading both from `event.eps`, while
+// `Unpolarized` drops Φ entirely so both reflectivities enter with equal weight.
+#[derive(Clone, Debug, Default)]
+pub enum Polarization {
+    #[default]
+    Event,
+    Unpolarized,
+    Fixed {
+        angle: f64,
+        magnitude: f64,
+    },
+    Table {
+        angle: f64,
+        energies: Vec<f64>,
+        magnitudes: Vec<f64>,
+    },
+    PerRun {
+        runs: Vec<u32>,
+        models: HashMap<u32, Polarization>,
+    },
+}
+
+fn read_columns(path: &Path) -> Result<Vec<Vec<String>>, GluexError> {
+    let contents = fs::read_to_string(path).map_err(|err| GluexError::PolarizationFile {
+        path: path.display().to_string(),
+        reason: err.to_string(),
+    })?;
+    Ok(contents
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|column| !column.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .collect())
+}
+
+fn parse_column<T: FromStr>(path: &Path, column: Option<&String>) -> Result<T, GluexError> {
+    column
+        .and_then(|column| column.parse().ok())
+        .ok_or_else(|| GluexError::PolarizationFile {
+            path: path.display().to_string(),
+            reason: format!("unable to parse {:?}", column),
+        })
+}
+
+impl Polarization {
+    pub fn fixed(angle: f64, magnitude: f64) -> Self {
+        Self::Fixed {
+            angle: angle.to_radians(),
+            magnitude,
+        }
+    }
+
+    pub fn orientation(orientation: Orientation, magnitude: f64) -> Self {
+        match orientation {
+            Orientation::Amo => Self::Unpolarized,
+            _ => Self::Fixed {
+                angle: orientation.angle(),
+                magnitude,
+            },
+        }
+    }
+
+    // Reads "energy polarization" pairs, one per line.
+    pub fn table<P: AsRef<Path>>(angle: f64, path: P) -> Result<Self, GluexError> {
+        let path = path.as_ref();
+        let mut rows = read_columns(path)?
+            .iter()
+            .map(|row| {
+                Ok((
+                    parse_column(path, row.first())?,
+                    parse_column(path, row.get(1))?,
+                ))
+            })
+            .collect::<Result<Vec<(f64, f64)>, GluexError>>()?;
+        if rows.is_empty() {
+            return Err(GluexError::PolarizationFile {
+                path: path.display().to_string(),
+                reason: "the table is empty".to_string(),
+            });
+        }
+        rows.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (energies, magnitudes) = rows.into_iter().unzip();
+        Ok(Self::Table {
+            angle: angle.to_radians(),
+            energies,
+            magnitudes,
+        })
+    }
+
+    // Reads "run angle polarization" rows, where the last column is either a fixed degree of
+    // polarization or the path to an energy table (relative to the run file). An angle of "AMO"
+    // marks an unpolarized run. `runs` gives the run number of each event in the dataset.
+    pub fn per_run<P: AsRef<Path>>(runs: &[u32], path: P) -> Result<Self, GluexError> {
+        let path = path.as_ref();
+        let directory = path.parent().unwrap_or(Path::new(""));
+        let models = read_columns(path)?
+            .iter()
+            .map(|row| {
+                let run = parse_column(path, row.first())?;
+                if row.get(1).is_some_and(|column| {
+                    column.parse::<Orientation>().ok() == Some(Orientation::Amo)
+                }) {
+                    return Ok((run, Self::Unpolarized));
+                }
+                let angle = parse_column(path, row.get(1))?;
+                let model = match row.get(2).map(|column| column.parse::<f64>()) {
+                    Some(Ok(magnitude)) => Self::fixed(angle, magnitude),
+                    Some(Err(_)) => Self::table(angle, directory.join(&row[2]))?,
+                    None => {
+                        return Err(GluexError::PolarizationFile {
+                            path: path.display().to_string(),
+                            reason: format!("run {} has no polarization column", run),
+                        })
+                    }
+                };
+                Ok((run, model))
+            })
+            .collect::<Result<HashMap<u32, Polarization>, GluexError>>()?;
+        Ok(Self::PerRun {
+            runs: runs.to_vec(),
+            models,
+        })
+    }
+
+    // `PerRun` looks up the run of each event by index, so its run list has to cover the dataset.
+    pub fn check_dataset(&self, dataset: &Dataset) -> Result<(), GluexError> {
+        if let Self::PerRun { runs, .. } = self {
+            let events = dataset.events.read().len();
+            if runs.len() < events {
+                return Err(GluexError::MissingRuns {
+                    found: runs.len(),
+                    expected: events,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn angle_and_magnitude(&self, event: &Event) -> Result<(f64, f64), GluexError> {
+        match self {
+            Self::Event => Ok((event.eps.y.atan2(event.eps.x), event.eps.norm())),
+            Self::Unpolarized => Ok((0.0, 0.0)),
+            Self::Fixed { angle, magnitude } => Ok((*angle, *magnitude)),
+            Self::Table {
+                angle,
+                energies,
+                magnitudes,
+            } => {
+                let energy = event.beam_p4.e();
+                let k = energies.partition_point(|&e_k| e_k <= energy);
+                if k == 0 {
+                    return Ok((*angle, magnitudes[0]));
+                }
+                if k == energies.len() {
+                    return Ok((*angle, magnitudes[k - 1]));
+                }
+                let t = (energy - energies[k - 1]) / (energies[k] - energies[k - 1]);
+                Ok((*angle, magnitudes[k - 1] * (1.0 - t) + magnitudes[k] * t))
+            }
+            Self::PerRun { runs, models } => {
+                let run = *runs.get(event.index).ok_or(GluexError::MissingRuns {
+                    found: runs.len(),
+                    expected: event.index + 1,
+                })?;
+                models
+                    .get(&run)
+                    .ok_or(GluexError::UnknownRun(run))?
+                    .angle_and_magnitude(event)
+            }
+        }
+    }
+
+    pub fn eps(&self, event: &Event) -> Result<Vector3<f64>, GluexError> {
+        let (angle, magnitude) = self.angle_and_magnitude(event)?;
+        Ok(Vector3::new(angle.cos(), angle.sin(), 0.0).scale(magnitude))
+    }
+
+    // Returns the angle between the polarization vector and the production plane (with normal
+    // `y`) along with the degree of polarization.
+    pub fn big_phi(&self, y: &Vector3<f64>, event: &Event) -> Result<(f64, f64), GluexError> {
+        match self {
+            Self::Unpolarized => return Ok((0.0, 0.0)),
+            Self::PerRun { runs, models } => {
+                let run = *runs.get(event.index).ok_or(GluexError::MissingRuns {
+                    found: runs.len(),
+                    expected: event.index + 1,
+                })?;
+                return models
+                    .get(&run)
+                    .ok_or(GluexError::UnknownRun(run))?
+                    .big_phi(y, event);
+            }
+            _ => {}
+        }
+        let (angle, magnitude) = self.angle_and_magnitude(event)?;
+        let eps = Vector3::new(angle.cos(), angle.sin(), 0.0);
+        let big_phi = y
+            .dot(&eps)
+            .atan2(event.beam_p4.momentum().normalize().dot(&eps.cross(y)));
+        Ok((big_phi, magnitude))
+    }
+}
+
+// The signed degree of circular polarization λ·P_c, from either a fixed beam helicity (for
+// datasets split by helicity state) or one helicity per event.
+#[derive(Clone, Debug)]
+pub enum CircularPolarization {
+    Fixed { helicity: f64, degree: f64 },
+    PerEvent { helicities: Vec<f64>, degree: f64 },
+}
+
+impl CircularPolarization {
+    pub fn check_dataset(&self, dataset: &Dataset) -> Result<(), GluexError> {
+        if let Self::PerEvent { helicities, .. } = self {
+            let events = dataset.events.read().len();
+            if helicities.len() < events {
+                return Err(GluexError::MissingHelicities {
+                    found: helicities.len(),
+                    expected: events,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn value(&self, event: &Event) -> Result<f64, GluexError> {
+        match self {
+            Self::Fixed { helicity, degree } => Ok(helicity * degree),
+            Self::PerEvent { helicities, degree } => helicities
+                .get(event.index)
+                .map(|helicity| helicity * degree)
+                .ok_or(GluexError::MissingHelicities {
+                    found: helicities.len(),
+                    expected: event.index + 1,
+                }),
+        }
+    }
+}
+
+#[pyclass(name = "Polarization")]
+#[derive(Clone)]
+pub struct PyPolarization(pub Polarization);
+
+#[pymethods]
+impl PyPolarization {
+    #[staticmethod]
+    fn event() -> Self {
+        Self(Polarization::Event)
+    }
+
+    #[staticmethod]
+    fn unpolarized() -> Self {
+        Self(Polarization::Unpolarized)
+    }
+
+    #[staticmethod]
+    fn fixed(angle: f64, magnitude: f64) -> Self {
+        Self(Polarization::fixed(angle, magnitude))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (orientation, magnitude=1.0))]
+    fn orientation(orientation: &str, magnitude: f64) -> PyResult<Self> {
+        Ok(Self(Polarization::orientation(
+            <Orientation as FromStr>::from_str(orientation)?,
+            magnitude,
+        )))
+    }
+
+    #[staticmethod]
+    fn table(angle: f64, path: &str) -> PyResult<Self> {
+        Ok(Self(Polarization::table(angle, path)?))
+    }
+
+    #[staticmethod]
+    fn per_run(runs: Vec<u32>, path: &str) -> PyResult<Self> {
+        Ok(Self(Polarization::per_run(&runs, path)?))
+    }
+}
+
+#[pyclass(name = "CircularPolarization")]
+#[derive(Clone)]
+pub struct PyCircularPolarization(pub CircularPolarization);
+
+#[pymethods]
+impl PyCircularPolarization {
+    #[staticmethod]
+    fn fixed(helicity: f64, degree: f64) -> Self {
+        Self(CircularPolarization::Fixed { helicity, degree })
+    }
+
+    #[staticmethod]
+    fn per_event(helicities: Vec<f64>, degree: f64) -> Self {
+        Self(CircularPolarization::PerEvent { helicities, degree })
+    }
+}
+
+pub fn pyo3_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyPolarization>()?;
+    m.add_class::<PyCircularPolarization>()?;
+    Ok(())
+}
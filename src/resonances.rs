@@ -1,7 +1,14 @@
+use crate::errors::GluexError;
 use crate::utils::blatt_weisskopf;
+use crate::utils::blatt_weisskopf_log_derivative;
 use crate::utils::breakup_momentum;
+use crate::utils::breakup_momentum_log_derivative;
+use crate::utils::Gradient;
+use crate::utils::PyGradient;
 use pyo3::prelude::*;
 use std::f64::consts::PI;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 use nalgebra::{SMatrix, SVector};
 use rayon::prelude::*;
@@ -12,6 +19,7 @@ pub struct BreitWigner {
     p1_indices: Vec<usize>,
     p2_indices: Vec<usize>,
     l: usize,
+    r: f64,
     m: Vec<f64>,
     m1: Vec<f64>,
     m2: Vec<f64>,
@@ -24,9 +32,14 @@ impl BreitWigner {
             p1_indices: p1_indices.into(),
             p2_indices: p2_indices.into(),
             l,
+            r: 1.0,
             ..Default::default()
         }
     }
+    pub fn with_radius(mut self, r: f64) -> Self {
+        self.r = r;
+        self
+    }
 }
 impl Node for BreitWigner {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
@@ -49,7 +62,7 @@ impl Node for BreitWigner {
                 let m1 = p1.m();
                 let m2 = p2.m();
                 let q = breakup_momentum(m, m1, m2);
-                let f = blatt_weisskopf(m, m1, m2, self.l);
+                let f = blatt_weisskopf(m, m1, m2, self.l, self.r);
                 (m, (m1, (m2, (q, f))))
             })
             .unzip();
@@ -64,10 +77,10 @@ impl Node for BreitWigner {
         let f = self.f[event.index];
         let m0 = parameters[0];
         let g0 = parameters[1];
-        let f0 = blatt_weisskopf(m0, m1, m2, self.l);
+        let f0 = blatt_weisskopf(m0, m1, m2, self.l, self.r);
         let q0 = breakup_momentum(m0, m1, m2);
         let g = g0 * (m0 / m) * (q / q0) * (f.powi(2) / f0.powi(2));
-        Ok(f * (m0 * g0 / PI) / Complex64::new(m0.powi(2) - m.powi(2), -1.0 * m0 * g))
+        Ok(f * (m0 * g0 / PI) / Complex64::new(m0.powi(2) - m.powi(2), -m0 * g))
     }
 
     fn parameters(&self) -> Vec<String> {
@@ -75,11 +88,144 @@ impl Node for BreitWigner {
     }
 }
 
+impl Gradient for BreitWigner {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let m = self.m[event.index];
+        let m1 = self.m1[event.index];
+        let m2 = self.m2[event.index];
+        let q = self.q[event.index];
+        let f = self.f[event.index];
+        let m0 = parameters[0];
+        let g0 = parameters[1];
+        let f0 = blatt_weisskopf(m0, m1, m2, self.l, self.r);
+        let q0 = breakup_momentum(m0, m1, m2);
+        let g_per_g0 = (m0 / m) * (q / q0) * (f.powi(2) / f0.powi(2));
+        let g = g0 * g_per_g0;
+        let dg_dm0 = g
+            * (1.0 / m0
+                - breakup_momentum_log_derivative(m0, m1, m2)
+                - 2.0 * blatt_weisskopf_log_derivative(m0, m1, m2, self.l, self.r));
+        let d = Complex64::new(m0.powi(2) - m.powi(2), -m0 * g);
+        let amplitude = f * (m0 * g0 / PI) / d;
+        let dd_dm0 = Complex64::new(2.0 * m0, -(g + m0 * dg_dm0));
+        let dd_dg0 = Complex64::new(0.0, -m0 * g_per_g0);
+        Ok(vec![
+            f * (g0 / PI) / d - amplitude * dd_dm0 / d,
+            f * (m0 / PI) / d - amplitude * dd_dg0 / d,
+        ])
+    }
+}
+
+// Every K-matrix amplitude is linear in its couplings, so the derivatives with respect to the
+// real and imaginary parts of each beta are just the folded P-vector terms (times i).
+fn beta_gradient<const R: usize>(pvector: &SVector<Complex64, R>) -> Vec<Complex64> {
+    pvector
+        .iter()
+        .flat_map(|term| [*term, Complex64::i() * term])
+        .collect()
+}
+
 #[derive(Clone, Copy)]
 pub struct AdlerZero {
     pub s_0: f64,
     pub s_norm: f64,
 }
+
+#[derive(Clone, Copy, Debug, Default)]
+pub enum PhaseSpace {
+    Rho,
+    #[default]
+    ChewMandelstam,
+    FourPi,
+}
+
+impl FromStr for PhaseSpace {
+    type Err = GluexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "rho" => Ok(PhaseSpace::Rho),
+            "simple" => Ok(PhaseSpace::Rho),
+            "chew-mandelstam" => Ok(PhaseSpace::ChewMandelstam),
+            "cm" => Ok(PhaseSpace::ChewMandelstam),
+            "4pi" => Ok(PhaseSpace::FourPi),
+            "four-pi" => Ok(PhaseSpace::FourPi),
+            _ => Err(GluexError::ParsePhaseSpace(s.to_string())),
+        }
+    }
+}
+
+impl PhaseSpace {
+    fn gauss_legendre() -> &'static [(f64, f64)] {
+        static NODES: OnceLock<Vec<(f64, f64)>> = OnceLock::new();
+        NODES.get_or_init(|| {
+            let n = 32;
+            (1..=n)
+                .map(|i| {
+                    let mut x = f64::cos(PI * (i as f64 - 0.25) / (n as f64 + 0.5));
+                    let mut dp = 1.0;
+                    for _ in 0..100 {
+                        let (mut p0, mut p1) = (1.0, x);
+                        for k in 2..=n {
+                            let p2 = ((2 * k - 1) as f64 * x * p1 - (k - 1) as f64 * p0) / k as f64;
+                            p0 = p1;
+                            p1 = p2;
+                        }
+                        dp = n as f64 * (x * p1 - p0) / (x * x - 1.0);
+                        let dx = p1 / dp;
+                        x -= dx;
+                        if dx.abs() < 1e-15 {
+                            break;
+                        }
+                    }
+                    (x, 2.0 / ((1.0 - x * x) * dp * dp))
+                })
+                .collect()
+        })
+    }
+
+    fn rho_real(s: f64, m1: f64, m2: f64) -> f64 {
+        f64::sqrt(f64::max(
+            (1.0 - (m1 + m2).powi(2) / s) * (1.0 - (m1 - m2).powi(2) / s),
+            0.0,
+        ))
+    }
+
+    // Four-body phase space built from two-body pieces, (pi pi)(pi pi), integrated over both
+    // pair masses and normalized to the massless result s^2 / 12 so that it tends to 1.
+    fn four_pi_rho(s: f64, m: f64) -> f64 {
+        let sqrt_s = s.sqrt();
+        if sqrt_s <= 4.0 * m {
+            return 0.0;
+        }
+        let nodes = Self::gauss_legendre();
+        let (mu1_min, mu1_max) = (2.0 * m, sqrt_s - 2.0 * m);
+        let integral: f64 = nodes
+            .iter()
+            .map(|(x1, w1)| {
+                let mu1 = 0.5 * (mu1_max + mu1_min) + 0.5 * (mu1_max - mu1_min) * x1;
+                let (mu2_min, mu2_max) = (2.0 * m, sqrt_s - mu1);
+                let inner: f64 = nodes
+                    .iter()
+                    .map(|(x2, w2)| {
+                        let mu2 = 0.5 * (mu2_max + mu2_min) + 0.5 * (mu2_max - mu2_min) * x2;
+                        w2 * 4.0
+                            * mu1
+                            * mu2
+                            * Self::rho_real(s, mu1, mu2)
+                            * Self::rho_real(mu1 * mu1, m, m)
+                            * Self::rho_real(mu2 * mu2, m, m)
+                    })
+                    .sum();
+                w1 * 0.5 * (mu2_max - mu2_min) * inner
+            })
+            .sum::<f64>()
+            * 0.5
+            * (mu1_max - mu1_min);
+        12.0 * integral / (s * s)
+    }
+}
+
 struct KMatrixConstants<const C: usize, const R: usize> {
     g: SMatrix<f64, C, R>,
     c: SMatrix<f64, C, C>,
@@ -87,7 +233,11 @@ struct KMatrixConstants<const C: usize, const R: usize> {
     m2s: [f64; C],
     mrs: [f64; R],
     adler_zero: Option<AdlerZero>,
+    // Background terms beyond the constant c, Σ c_n s̃^n (n = 1, 2, ...) with s̃ = s / s_norm - 1.
+    c_expansion: Option<(f64, Vec<SMatrix<f64, C, C>>)>,
     l: usize,
+    r: f64,
+    phase_space: [PhaseSpace; C],
 }
 
 impl<const C: usize, const R: usize> KMatrixConstants<C, R> {
@@ -102,30 +252,61 @@ impl<const C: usize, const R: usize> KMatrixConstants<C, R> {
     fn rho(s: f64, m1: f64, m2: f64) -> Complex64 {
         Complex64::from(Self::chi_plus(s, m1, m2) * Self::chi_minus(s, m1, m2)).sqrt()
     }
+
+    // Above threshold Im C = -ρ, as in `Rho`. Below it C is real: between the pseudo-threshold
+    // and the threshold ρ² < 0 and the logarithm becomes an arctangent, while below the
+    // pseudo-threshold ρ is real again and the logarithm is taken of a positive ratio.
+    fn chew_mandelstam(s: f64, m1: f64, m2: f64) -> Complex64 {
+        let chi_plus = Self::chi_plus(s, m1, m2);
+        let rho_sq = chi_plus * Self::chi_minus(s, m1, m2);
+        let mass_term = chi_plus / PI * ((m2 - m1) / (m1 + m2)) * (m2 / m1).ln();
+        if chi_plus > 0.0 {
+            let rho = rho_sq.sqrt();
+            Complex64::new(
+                rho / PI * ((chi_plus + rho) / (rho - chi_plus)).ln() + mass_term,
+                -rho,
+            )
+        } else if rho_sq < 0.0 {
+            let kappa = (-rho_sq).sqrt();
+            Complex64::from(2.0 * kappa / PI * (kappa / -chi_plus).atan() + mass_term)
+        } else {
+            let rho = rho_sq.sqrt();
+            Complex64::from(rho / PI * ((chi_plus + rho) / (chi_plus - rho)).ln() + mass_term)
+        }
+    }
     fn c_matrix(&self, s: f64) -> SMatrix<Complex64, C, C> {
         SMatrix::from_diagonal(&SVector::from_fn(|i, _| {
-            Self::rho(s, self.m1s[i], self.m2s[i]) / PI
-                * ((Self::chi_plus(s, self.m1s[i], self.m2s[i])
-                    + Self::rho(s, self.m1s[i], self.m2s[i]))
-                    / (Self::chi_plus(s, self.m1s[i], self.m2s[i])
-                        - Self::rho(s, self.m1s[i], self.m2s[i])))
-                .ln()
-                + Self::chi_plus(s, self.m1s[i], self.m2s[i]) / PI
-                    * ((self.m2s[i] - self.m1s[i]) / (self.m1s[i] + self.m2s[i]))
-                    * (self.m2s[i] / self.m1s[i]).ln()
+            let (m1, m2) = (self.m1s[i], self.m2s[i]);
+            match self.phase_space[i] {
+                PhaseSpace::Rho => -Complex64::i() * Self::rho(s, m1, m2),
+                PhaseSpace::ChewMandelstam => Self::chew_mandelstam(s, m1, m2),
+                PhaseSpace::FourPi => {
+                    -Complex64::i() * PhaseSpace::four_pi_rho(s, 0.25 * (m1 + m2))
+                }
+            }
         }))
     }
-    fn barrier_factor(s: f64, m1: f64, m2: f64, mr: f64, l: usize) -> f64 {
-        blatt_weisskopf(s.sqrt(), m1, m2, l) / blatt_weisskopf(mr, m1, m2, l)
+    fn barrier_factor(s: f64, m1: f64, m2: f64, mr: f64, l: usize, r: f64) -> f64 {
+        blatt_weisskopf(s.sqrt(), m1, m2, l, r) / blatt_weisskopf(mr, m1, m2, l, r)
     }
     fn barrier_matrix(&self, s: f64) -> SMatrix<f64, C, R> {
         SMatrix::from_fn(|i, a| {
-            Self::barrier_factor(s, self.m1s[i], self.m2s[i], self.mrs[a], self.l)
+            Self::barrier_factor(s, self.m1s[i], self.m2s[i], self.mrs[a], self.l, self.r)
+        })
+    }
+
+    fn background(&self, s: f64) -> SMatrix<f64, C, C> {
+        self.c_expansion.as_ref().map_or(self.c, |(s_norm, terms)| {
+            let s_tilde = s / s_norm - 1.0;
+            terms.iter().enumerate().fold(self.c, |c, (n, term)| {
+                c + term.scale(s_tilde.powi(n as i32 + 1))
+            })
         })
     }
 
     fn k_matrix(&self, s: f64) -> SMatrix<Complex64, C, C> {
         let bf = self.barrier_matrix(s);
+        let c = self.background(s);
         SMatrix::from_fn(|i, j| {
             (0..R)
                 .map(|a| {
@@ -133,43 +314,191 @@ impl<const C: usize, const R: usize> KMatrixConstants<C, R> {
                         bf[(i, a)]
                             * bf[(j, a)]
                             * (self.g[(i, a)] * self.g[(j, a)] / (self.mrs[a].powi(2) - s)
-                                + self.c[(i, j)]),
+                                + c[(i, j)]),
                     )
                 })
                 .sum::<Complex64>()
                 * self.adler_zero.map_or(1.0, |az| (s - az.s_0) / az.s_norm)
         })
     }
-    fn ikc_inv(&self, s: f64, channel: usize) -> SVector<Complex64, C> {
+    fn ikc_inv(&self, s: f64, channel: usize) -> Result<SVector<Complex64, C>, GluexError> {
         let c_mat = self.c_matrix(s);
         let i_mat = SMatrix::<Complex64, C, C>::identity();
         let k_mat = self.k_matrix(s);
         let ikc_mat = i_mat + k_mat * c_mat;
-        let ikc_inv_mat = ikc_mat.try_inverse().unwrap();
-        ikc_inv_mat.row(channel).transpose()
+        let ikc_inv_mat = ikc_mat
+            .try_inverse()
+            .ok_or(GluexError::SingularKMatrix(s))?;
+        Ok(ikc_inv_mat.row(channel).transpose())
     }
 
-    fn p_vector(
-        betas: &SVector<Complex64, R>,
-        pvector_constants: &SMatrix<Complex64, C, R>,
-    ) -> SVector<Complex64, C> {
-        SVector::<Complex64, C>::from_fn(|j, _| {
-            (0..R).map(|a| betas[a] * pvector_constants[(j, a)]).sum()
+    fn pvector_constants(&self, s: f64) -> SMatrix<Complex64, C, R> {
+        let barrier_mat = self.barrier_matrix(s);
+        SMatrix::from_fn(|i, a| {
+            Complex64::from(barrier_mat[(i, a)]) * self.g[(i, a)] / (self.mrs[a].powi(2) - s)
         })
     }
 
-    pub fn calculate_k_matrix(
-        betas: &SVector<Complex64, R>,
+    fn folded_pvector_constants(
+        &self,
+        s: f64,
         ikc_inv_vec: &SVector<Complex64, C>,
-        pvector_constants_mat: &SMatrix<Complex64, C, R>,
-    ) -> Complex64 {
-        ikc_inv_vec.dot(&Self::p_vector(betas, pvector_constants_mat))
+    ) -> SVector<Complex64, R> {
+        self.pvector_constants(s).tr_mul(ikc_inv_vec)
+    }
+
+    fn precalculate(
+        &self,
+        dataset: &Dataset,
+        channel: usize,
+        tolerance: Option<f64>,
+    ) -> Result<Vec<SVector<Complex64, R>>, GluexError> {
+        if channel >= C {
+            return Err(GluexError::InvalidChannel {
+                channel,
+                channels: C,
+            });
+        }
+        let events = dataset.events.read();
+        let s_values: Vec<f64> = events
+            .par_iter()
+            .map(|event| (event.daughter_p4s[0] + event.daughter_p4s[1]).m2())
+            .collect();
+        if let Some(tolerance) = tolerance {
+            if !(tolerance.is_finite() && tolerance > 0.0) {
+                return Err(GluexError::InvalidTolerance(tolerance));
+            }
+        }
+        match tolerance {
+            Some(tolerance) if !s_values.is_empty() => {
+                let s_min = s_values.iter().copied().fold(f64::INFINITY, f64::min);
+                let s_max = s_values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                let grid = KMatrixGrid::new(s_min, s_max, tolerance, |s| self.ikc_inv(s, channel))?;
+                Ok(s_values
+                    .par_iter()
+                    .map(|&s| self.folded_pvector_constants(s, &grid.interpolate(s)))
+                    .collect())
+            }
+            _ => s_values
+                .par_iter()
+                .map(|&s| Ok(self.folded_pvector_constants(s, &self.ikc_inv(s, channel)?)))
+                .collect(),
+        }
+    }
+}
+
+// Piecewise-linear table of a row of (I + KC)^{-1} over s. Every interval is bisected until
+// the linear interpolation misses the true value by less than the tolerance at its midpoint and
+// at both quarter points. This samples the error rather than bounding it, so a narrow feature
+// between the samples can still be missed by more than the tolerance. The table is capped at
+// MAX_POINTS entries so that an unreachable tolerance fails instead of exhausting memory.
+struct KMatrixGrid<const C: usize> {
+    s: Vec<f64>,
+    values: Vec<SVector<Complex64, C>>,
+}
+
+impl<const C: usize> KMatrixGrid<C> {
+    const INITIAL_INTERVALS: usize = 64;
+    const MAX_POINTS: usize = 1 << 20;
+
+    fn new<F>(s_min: f64, s_max: f64, tolerance: f64, f: F) -> Result<Self, GluexError>
+    where
+        F: Fn(f64) -> Result<SVector<Complex64, C>, GluexError>,
+    {
+        let mut s_grid = vec![s_min];
+        let mut values = vec![f(s_min)?];
+        if s_max <= s_min {
+            return Ok(Self { s: s_grid, values });
+        }
+        let min_width = (s_max - s_min) * f64::EPSILON.sqrt();
+        let width = (s_max - s_min) / Self::INITIAL_INTERVALS as f64;
+        for k in 1..=Self::INITIAL_INTERVALS {
+            let s_right = if k == Self::INITIAL_INTERVALS {
+                s_max
+            } else {
+                s_min + k as f64 * width
+            };
+            let mut stack = vec![(s_right, f(s_right)?)];
+            while let Some((s_b, f_b)) = stack.pop() {
+                if s_grid.len() + stack.len() > Self::MAX_POINTS {
+                    return Err(GluexError::GridTooLarge {
+                        tolerance,
+                        max_points: Self::MAX_POINTS,
+                    });
+                }
+                let s_a = s_grid[s_grid.len() - 1];
+                let f_a = values[values.len() - 1];
+                let s_mid = 0.5 * (s_a + s_b);
+                let f_mid = f(s_mid)?;
+                // the stored halves are checked at their own midpoints, the quarter points
+                let error = Self::miss(f_mid, f_a, f_b)
+                    .max(Self::miss(f(0.5 * (s_a + s_mid))?, f_a, f_mid))
+                    .max(Self::miss(f(0.5 * (s_mid + s_b))?, f_mid, f_b));
+                if error > tolerance && s_b - s_a > min_width {
+                    stack.push((s_b, f_b));
+                    stack.push((s_mid, f_mid));
+                } else {
+                    s_grid.push(s_mid);
+                    values.push(f_mid);
+                    s_grid.push(s_b);
+                    values.push(f_b);
+                }
+            }
+        }
+        Ok(Self { s: s_grid, values })
+    }
+
+    // largest distance between a true value and the midpoint of the chord around it
+    fn miss(
+        f_x: SVector<Complex64, C>,
+        f_l: SVector<Complex64, C>,
+        f_r: SVector<Complex64, C>,
+    ) -> f64 {
+        (f_x - (f_l + f_r).unscale(2.0))
+            .iter()
+            .map(|d| d.norm())
+            .fold(0.0, f64::max)
+    }
+
+    fn interpolate(&self, s: f64) -> SVector<Complex64, C> {
+        let k = self.s.partition_point(|&s_k| s_k <= s);
+        if k == 0 {
+            return self.values[0];
+        }
+        if k == self.s.len() {
+            return self.values[k - 1];
+        }
+        let t = (s - self.s[k - 1]) / (self.s[k] - self.s[k - 1]);
+        self.values[k - 1].scale(1.0 - t) + self.values[k].scale(t)
     }
 }
+
+macro_rules! kmatrix_builders {
+    ($kmatrix:ident, $channels:literal) => {
+        impl $kmatrix {
+            // the tolerance is checked at sampled points of each grid interval, not guaranteed
+            // everywhere (see KMatrixGrid)
+            pub fn interpolated(mut self, tolerance: f64) -> Self {
+                self.3 = Some(tolerance);
+                self
+            }
+            pub fn with_phase_space(mut self, phase_space: [PhaseSpace; $channels]) -> Self {
+                self.1.phase_space = phase_space;
+                self
+            }
+            pub fn with_radius(mut self, r: f64) -> Self {
+                self.1.r = r;
+                self
+            }
+        }
+    };
+}
+
 pub struct KMatrixF0(
     usize,
     KMatrixConstants<5, 5>,
-    Vec<(SVector<Complex64, 5>, SMatrix<Complex64, 5, 5>)>,
+    Vec<SVector<Complex64, 5>>,
+    Option<f64>,
 );
 #[rustfmt::skip]
 impl KMatrixF0 {
@@ -197,28 +526,20 @@ impl KMatrixF0 {
                     s_0: 0.0091125,
                     s_norm: 1.0,
                 }),
+                c_expansion: None,
                 l: 0,
+                r: 1.0,
+                phase_space: [PhaseSpace::ChewMandelstam; 5],
             },
-            Vec::default())
+            Vec::default(),
+            None)
     }
 }
+kmatrix_builders!(KMatrixF0, 5);
 
 impl Node for KMatrixF0 {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
-        self.2 = dataset
-            .events
-            .read()
-            .par_iter()
-            .map(|event| {
-                let s = (event.daughter_p4s[0] + event.daughter_p4s[1]).m2();
-                let barrier_mat = self.1.barrier_matrix(s);
-                let pvector_constants = SMatrix::<Complex64, 5, 5>::from_fn(|i, a| {
-                    Complex64::from(barrier_mat[(i, a)]) * self.1.g[(i, a)]
-                        / (self.1.mrs[a].powi(2) - s)
-                });
-                (self.1.ikc_inv(s, self.0), pvector_constants)
-            })
-            .collect();
+        self.2 = self.1.precalculate(dataset, self.0, self.3)?;
         Ok(())
     }
     fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
@@ -229,12 +550,7 @@ impl Node for KMatrixF0 {
             Complex64::new(parameters[6], parameters[7]),
             Complex64::new(parameters[8], parameters[9]),
         );
-        let (ikc_inv_vec, pvector_constants_mat) = self.2[event.index];
-        Ok(KMatrixConstants::calculate_k_matrix(
-            &betas,
-            &ikc_inv_vec,
-            &pvector_constants_mat,
-        ))
+        Ok(betas.dot(&self.2[event.index]))
     }
     fn parameters(&self) -> Vec<String> {
         vec![
@@ -251,10 +567,17 @@ impl Node for KMatrixF0 {
         ]
     }
 }
+
+impl Gradient for KMatrixF0 {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(beta_gradient(&self.2[event.index]))
+    }
+}
 pub struct KMatrixF2(
     usize,
     KMatrixConstants<4, 4>,
-    Vec<(SVector<Complex64, 4>, SMatrix<Complex64, 4, 4>)>,
+    Vec<SVector<Complex64, 4>>,
+    Option<f64>,
 );
 #[rustfmt::skip]
 impl KMatrixF2 {
@@ -277,28 +600,20 @@ impl KMatrixF2 {
                 m2s: [0.13498, 0.26995, 0.49761, 0.54786],
                 mrs: [1.15299, 1.48359, 1.72923, 1.96700],
                 adler_zero: None,
+                c_expansion: None,
                 l: 2,
+                r: 1.0,
+                phase_space: [PhaseSpace::ChewMandelstam; 4],
             },
-            Vec::default())
+            Vec::default(),
+            None)
     }
 }
+kmatrix_builders!(KMatrixF2, 4);
 
 impl Node for KMatrixF2 {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
-        self.2 = dataset
-            .events
-            .read()
-            .par_iter()
-            .map(|event| {
-                let s = (event.daughter_p4s[0] + event.daughter_p4s[1]).m2();
-                let barrier_mat = self.1.barrier_matrix(s);
-                let pvector_constants = SMatrix::<Complex64, 4, 4>::from_fn(|i, a| {
-                    Complex64::from(barrier_mat[(i, a)]) * self.1.g[(i, a)]
-                        / (self.1.mrs[a].powi(2) - s)
-                });
-                (self.1.ikc_inv(s, self.0), pvector_constants)
-            })
-            .collect();
+        self.2 = self.1.precalculate(dataset, self.0, self.3)?;
         Ok(())
     }
     fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
@@ -308,12 +623,7 @@ impl Node for KMatrixF2 {
             Complex64::new(parameters[4], parameters[5]),
             Complex64::new(parameters[6], parameters[7]),
         );
-        let (ikc_inv_vec, pvector_constants_mat) = self.2[event.index];
-        Ok(KMatrixConstants::calculate_k_matrix(
-            &betas,
-            &ikc_inv_vec,
-            &pvector_constants_mat,
-        ))
+        Ok(betas.dot(&self.2[event.index]))
     }
     fn parameters(&self) -> Vec<String> {
         vec![
@@ -329,10 +639,17 @@ impl Node for KMatrixF2 {
     }
 }
 
+impl Gradient for KMatrixF2 {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(beta_gradient(&self.2[event.index]))
+    }
+}
+
 pub struct KMatrixA0(
     usize,
     KMatrixConstants<2, 2>,
-    Vec<(SVector<Complex64, 2>, SMatrix<Complex64, 2, 2>)>,
+    Vec<SVector<Complex64, 2>>,
+    Option<f64>,
 );
 #[rustfmt::skip]
 impl KMatrixA0 {
@@ -351,28 +668,20 @@ impl KMatrixA0 {
                 m2s: [0.54786, 0.49761],
                 mrs: [0.95395, 1.26767],
                 adler_zero: None,
+                c_expansion: None,
                 l: 0,
+                r: 1.0,
+                phase_space: [PhaseSpace::ChewMandelstam; 2],
             },
-            Vec::default())
+            Vec::default(),
+            None)
     }
 }
+kmatrix_builders!(KMatrixA0, 2);
 
 impl Node for KMatrixA0 {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
-        self.2 = dataset
-            .events
-            .read()
-            .par_iter()
-            .map(|event| {
-                let s = (event.daughter_p4s[0] + event.daughter_p4s[1]).m2();
-                let barrier_mat = self.1.barrier_matrix(s);
-                let pvector_constants = SMatrix::<Complex64, 2, 2>::from_fn(|i, a| {
-                    Complex64::from(barrier_mat[(i, a)]) * self.1.g[(i, a)]
-                        / (self.1.mrs[a].powi(2) - s)
-                });
-                (self.1.ikc_inv(s, self.0), pvector_constants)
-            })
-            .collect();
+        self.2 = self.1.precalculate(dataset, self.0, self.3)?;
         Ok(())
     }
     fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
@@ -380,12 +689,7 @@ impl Node for KMatrixA0 {
             Complex64::new(parameters[0], parameters[1]),
             Complex64::new(parameters[2], parameters[3]),
         );
-        let (ikc_inv_vec, pvector_constants_mat) = self.2[event.index];
-        Ok(KMatrixConstants::calculate_k_matrix(
-            &betas,
-            &ikc_inv_vec,
-            &pvector_constants_mat,
-        ))
+        Ok(betas.dot(&self.2[event.index]))
     }
     fn parameters(&self) -> Vec<String> {
         vec![
@@ -397,10 +701,17 @@ impl Node for KMatrixA0 {
     }
 }
 
+impl Gradient for KMatrixA0 {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(beta_gradient(&self.2[event.index]))
+    }
+}
+
 pub struct KMatrixA2(
     usize,
     KMatrixConstants<3, 2>,
-    Vec<(SVector<Complex64, 3>, SMatrix<Complex64, 3, 2>)>,
+    Vec<SVector<Complex64, 2>>,
+    Option<f64>,
 );
 #[rustfmt::skip]
 impl KMatrixA2 {
@@ -421,28 +732,20 @@ impl KMatrixA2 {
                 m2s: [0.54786, 0.49761, 0.95778],
                 mrs: [1.30080, 1.75351],
                 adler_zero: None,
+                c_expansion: None,
                 l: 2,
+                r: 1.0,
+                phase_space: [PhaseSpace::ChewMandelstam; 3],
             },
-            Vec::default())
+            Vec::default(),
+            None)
     }
 }
+kmatrix_builders!(KMatrixA2, 3);
 
 impl Node for KMatrixA2 {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
-        self.2 = dataset
-            .events
-            .read()
-            .par_iter()
-            .map(|event| {
-                let s = (event.daughter_p4s[0] + event.daughter_p4s[1]).m2();
-                let barrier_mat = self.1.barrier_matrix(s);
-                let pvector_constants = SMatrix::<Complex64, 3, 2>::from_fn(|i, a| {
-                    Complex64::from(barrier_mat[(i, a)]) * self.1.g[(i, a)]
-                        / (self.1.mrs[a].powi(2) - s)
-                });
-                (self.1.ikc_inv(s, self.0), pvector_constants)
-            })
-            .collect();
+        self.2 = self.1.precalculate(dataset, self.0, self.3)?;
         Ok(())
     }
     fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
@@ -450,12 +753,7 @@ impl Node for KMatrixA2 {
             Complex64::new(parameters[0], parameters[1]),
             Complex64::new(parameters[2], parameters[3]),
         );
-        let (ikc_inv_vec, pvector_constants_mat) = self.2[event.index];
-        Ok(KMatrixConstants::calculate_k_matrix(
-            &betas,
-            &ikc_inv_vec,
-            &pvector_constants_mat,
-        ))
+        Ok(betas.dot(&self.2[event.index]))
     }
     fn parameters(&self) -> Vec<String> {
         vec![
@@ -467,10 +765,17 @@ impl Node for KMatrixA2 {
     }
 }
 
+impl Gradient for KMatrixA2 {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(beta_gradient(&self.2[event.index]))
+    }
+}
+
 pub struct KMatrixRho(
     usize,
     KMatrixConstants<3, 2>,
-    Vec<(SVector<Complex64, 3>, SMatrix<Complex64, 3, 2>)>,
+    Vec<SVector<Complex64, 2>>,
+    Option<f64>,
 );
 #[rustfmt::skip]
 impl KMatrixRho {
@@ -491,28 +796,20 @@ impl KMatrixRho {
                 m2s: [0.13498, 0.26995, 0.49761],
                 mrs: [0.71093, 1.58660],
                 adler_zero: None,
+                c_expansion: None,
                 l: 1,
+                r: 1.0,
+                phase_space: [PhaseSpace::ChewMandelstam; 3],
             },
-            Vec::default())
+            Vec::default(),
+            None)
     }
 }
+kmatrix_builders!(KMatrixRho, 3);
 
 impl Node for KMatrixRho {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
-        self.2 = dataset
-            .events
-            .read()
-            .par_iter()
-            .map(|event| {
-                let s = (event.daughter_p4s[0] + event.daughter_p4s[1]).m2();
-                let barrier_mat = self.1.barrier_matrix(s);
-                let pvector_constants = SMatrix::<Complex64, 3, 2>::from_fn(|i, a| {
-                    Complex64::from(barrier_mat[(i, a)]) * self.1.g[(i, a)]
-                        / (self.1.mrs[a].powi(2) - s)
-                });
-                (self.1.ikc_inv(s, self.0), pvector_constants)
-            })
-            .collect();
+        self.2 = self.1.precalculate(dataset, self.0, self.3)?;
         Ok(())
     }
     fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
@@ -520,12 +817,7 @@ impl Node for KMatrixRho {
             Complex64::new(parameters[0], parameters[1]),
             Complex64::new(parameters[2], parameters[3]),
         );
-        let (ikc_inv_vec, pvector_constants_mat) = self.2[event.index];
-        Ok(KMatrixConstants::calculate_k_matrix(
-            &betas,
-            &ikc_inv_vec,
-            &pvector_constants_mat,
-        ))
+        Ok(betas.dot(&self.2[event.index]))
     }
     fn parameters(&self) -> Vec<String> {
         vec![
@@ -537,10 +829,17 @@ impl Node for KMatrixRho {
     }
 }
 
+impl Gradient for KMatrixRho {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(beta_gradient(&self.2[event.index]))
+    }
+}
+
 pub struct KMatrixPi1(
     usize,
     KMatrixConstants<2, 1>,
-    Vec<(SVector<Complex64, 2>, SMatrix<Complex64, 2, 1>)>,
+    Vec<SVector<Complex64, 1>>,
+    Option<f64>,
 );
 #[rustfmt::skip]
 impl KMatrixPi1 {
@@ -559,84 +858,371 @@ impl KMatrixPi1 {
                 m2s: [0.54786, 0.95778],
                 mrs: [1.38552],
                 adler_zero: None,
+                c_expansion: None,
                 l: 1,
+                r: 1.0,
+                phase_space: [PhaseSpace::ChewMandelstam; 2],
             },
-            Vec::default())
+            Vec::default(),
+            None)
     }
 }
+kmatrix_builders!(KMatrixPi1, 2);
 
 impl Node for KMatrixPi1 {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
-        self.2 = dataset
-            .events
-            .read()
-            .par_iter()
-            .map(|event| {
-                let s = (event.daughter_p4s[0] + event.daughter_p4s[1]).m2();
-                let barrier_mat = self.1.barrier_matrix(s);
-                let pvector_constants = SMatrix::<Complex64, 2, 1>::from_fn(|i, a| {
-                    Complex64::from(barrier_mat[(i, a)]) * self.1.g[(i, a)]
-                        / (self.1.mrs[a].powi(2) - s)
-                });
-                (self.1.ikc_inv(s, self.0), pvector_constants)
-            })
-            .collect();
+        self.2 = self.1.precalculate(dataset, self.0, self.3)?;
         Ok(())
     }
     fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
         let betas = SVector::<Complex64, 1>::new(Complex64::new(parameters[0], parameters[1]));
-        let (ikc_inv_vec, pvector_constants_mat) = self.2[event.index];
-        Ok(KMatrixConstants::calculate_k_matrix(
-            &betas,
-            &ikc_inv_vec,
-            &pvector_constants_mat,
-        ))
+        Ok(betas.dot(&self.2[event.index]))
     }
     fn parameters(&self) -> Vec<String> {
         vec!["pi1_1600 re".to_string(), "pi1_1600 im".to_string()]
     }
 }
 
-#[pyfunction(name = "BreitWigner")]
-fn breit_wigner(name: &str, p1_indices: Vec<usize>, p2_indices: Vec<usize>, l: usize) -> PyAmpOp {
-    Amplitude::new(
-        name,
-        Box::new(BreitWigner::new(&p1_indices, &p2_indices, l)),
-    )
-    .into()
+impl Gradient for KMatrixPi1 {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(beta_gradient(&self.2[event.index]))
+    }
 }
-#[pyfunction(name = "KMatrixA0")]
-fn kmatrix_a0(name: &str, channel: usize) -> PyAmpOp {
-    Amplitude::new(name, Box::new(KMatrixA0::new(channel))).into()
+
+pub struct KMatrixK0Star(
+    usize,
+    KMatrixConstants<2, 1>,
+    Vec<SVector<Complex64, 1>>,
+    Option<f64>,
+);
+#[rustfmt::skip]
+impl KMatrixK0Star {
+    // I = 1/2 Kπ S-wave from the FOCUS fit to D⁺ → K⁻π⁺π⁺, with channels (K⁺π⁻, K⁺η′) and the
+    // pole at s = 1.7919 GeV² (Link et al., Phys. Lett. B 653, 1 (2007)). The fit uses the simple
+    // phase space ρ = 2q/√s, so that is the default here.
+    pub fn new(channel: usize) -> Self {
+        Self(channel,
+             KMatrixConstants {
+                g: SMatrix::<f64, 2, 1>::new(
+                     0.31072,
+                    -0.02323,
+                ),
+                c: SMatrix::<f64, 2, 2>::new(
+                    0.79299, 0.15040,
+                    0.15040, 0.17054,
+                ),
+                m1s: [0.49368, 0.49368],
+                m2s: [0.13957, 0.95778],
+                mrs: [1.33862],
+                adler_zero: Some(AdlerZero {
+                    s_0: 0.23000,
+                    s_norm: 0.26320,
+                }),
+                c_expansion: Some((0.26320, vec![
+                    SMatrix::<f64, 2, 2>::new(
+                        -0.15099, -0.03800,
+                        -0.03800, -0.02190,
+                    ),
+                    SMatrix::<f64, 2, 2>::new(
+                        0.00811, 0.00000,
+                        0.00000, 0.00085655,
+                    ),
+                ])),
+                l: 0,
+                r: 1.0,
+                phase_space: [PhaseSpace::Rho; 2],
+            },
+            Vec::default(),
+            None)
+    }
 }
-#[pyfunction(name = "KMatrixA2")]
-fn kmatrix_a2(name: &str, channel: usize) -> PyAmpOp {
-    Amplitude::new(name, Box::new(KMatrixA2::new(channel))).into()
+kmatrix_builders!(KMatrixK0Star, 2);
+
+impl Node for KMatrixK0Star {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.2 = self.1.precalculate(dataset, self.0, self.3)?;
+        Ok(())
+    }
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        let betas = SVector::<Complex64, 1>::new(Complex64::new(parameters[0], parameters[1]));
+        Ok(betas.dot(&self.2[event.index]))
+    }
+    fn parameters(&self) -> Vec<String> {
+        vec!["k0star_1430 re".to_string(), "k0star_1430 im".to_string()]
+    }
 }
-#[pyfunction(name = "KMatrixF0")]
-fn kmatrix_f0(name: &str, channel: usize) -> PyAmpOp {
-    Amplitude::new(name, Box::new(KMatrixF0::new(channel))).into()
+
+impl Gradient for KMatrixK0Star {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(beta_gradient(&self.2[event.index]))
+    }
 }
-#[pyfunction(name = "KMatrixF2")]
-fn kmatrix_f2(name: &str, channel: usize) -> PyAmpOp {
-    Amplitude::new(name, Box::new(KMatrixF2::new(channel))).into()
+
+pub struct KMatrixKStar(
+    usize,
+    KMatrixConstants<2, 2>,
+    Vec<SVector<Complex64, 2>>,
+    Option<f64>,
+);
+#[rustfmt::skip]
+impl KMatrixKStar {
+    // K*(892)⁰ and K*(1410)⁰ in (K⁺π⁻, K⁺η′). The Kπ couplings reproduce the PDG masses and
+    // partial widths (Navas et al., Phys. Rev. D 110, 030001 (2024)) through mΓ = g²ρ with
+    // ρ = 2q/√s (also the default phase space), and the Kη′ couplings follow from SU(3) with the
+    // η-η′ mixing angle φ = 39.3° (Feldmann, Kroll and Stech, Phys. Rev. D 58, 114006 (1998)),
+    // g(Kη′)/g(Kπ) = (sin φ/√2 - cos φ)/√(3/2).
+    pub fn new(channel: usize) -> Self {
+        Self(channel,
+             KMatrixConstants {
+                g: SMatrix::<f64, 2, 2>::new(
+                     0.25526,  0.15815,
+                    -0.06794, -0.04209,
+                ),
+                c: SMatrix::<f64, 2, 2>::new(
+                    0.00000, 0.00000,
+                    0.00000, 0.00000,
+                ),
+                m1s: [0.49368, 0.49368],
+                m2s: [0.13957, 0.95778],
+                mrs: [0.89555, 1.41400],
+                adler_zero: None,
+                c_expansion: None,
+                l: 1,
+                r: 1.0,
+                phase_space: [PhaseSpace::Rho; 2],
+            },
+            Vec::default(),
+            None)
+    }
 }
-#[pyfunction(name = "KMatrixPi1")]
-fn kmatrix_pi1(name: &str, channel: usize) -> PyAmpOp {
-    Amplitude::new(name, Box::new(KMatrixPi1::new(channel))).into()
+kmatrix_builders!(KMatrixKStar, 2);
+
+impl Node for KMatrixKStar {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.2 = self.1.precalculate(dataset, self.0, self.3)?;
+        Ok(())
+    }
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        let betas = SVector::<Complex64, 2>::new(
+            Complex64::new(parameters[0], parameters[1]),
+            Complex64::new(parameters[2], parameters[3]),
+        );
+        Ok(betas.dot(&self.2[event.index]))
+    }
+    fn parameters(&self) -> Vec<String> {
+        vec![
+            "kstar_892 re".to_string(),
+            "kstar_892 im".to_string(),
+            "kstar_1410 re".to_string(),
+            "kstar_1410 im".to_string(),
+        ]
+    }
 }
-#[pyfunction(name = "KMatrixRho")]
-fn kmatrix_rho(name: &str, channel: usize) -> PyAmpOp {
-    Amplitude::new(name, Box::new(KMatrixRho::new(channel))).into()
+
+impl Gradient for KMatrixKStar {
+    fn gradient(&self, _parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(beta_gradient(&self.2[event.index]))
+    }
 }
 
+fn parse_phase_space<const C: usize>(
+    phase_space: &[String],
+) -> Result<[PhaseSpace; C], GluexError> {
+    let phase_space = phase_space
+        .iter()
+        .map(|p| <PhaseSpace as FromStr>::from_str(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    let found = phase_space.len();
+    phase_space
+        .try_into()
+        .map_err(|_| GluexError::InvalidPhaseSpaceCount { expected: C, found })
+}
+
+#[pyfunction]
+#[pyo3(name = "BreitWigner", signature = (name, p1_indices, p2_indices, l, radius=1.0))]
+fn breit_wigner(
+    name: &str,
+    p1_indices: Vec<usize>,
+    p2_indices: Vec<usize>,
+    l: usize,
+    radius: f64,
+) -> PyAmpOp {
+    Amplitude::new(
+        name,
+        Box::new(BreitWigner::new(&p1_indices, &p2_indices, l).with_radius(radius)),
+    )
+    .into()
+}
+
+#[pyfunction]
+#[pyo3(name = "BreitWignerGradient", signature = (p1_indices, p2_indices, l, radius=1.0))]
+fn breit_wigner_gradient(
+    p1_indices: Vec<usize>,
+    p2_indices: Vec<usize>,
+    l: usize,
+    radius: f64,
+) -> PyGradient {
+    PyGradient::new(BreitWigner::new(&p1_indices, &p2_indices, l).with_radius(radius))
+}
+
+macro_rules! kmatrix_pyfunction {
+    ($function:ident, $gradient_function:ident, $name:literal, $gradient_name:literal, $kmatrix:ident) => {
+        impl $kmatrix {
+            fn from_py(
+                channel: usize,
+                tolerance: Option<f64>,
+                phase_space: Option<Vec<String>>,
+                radius: f64,
+            ) -> PyResult<Self> {
+                let mut node = $kmatrix::new(channel).with_radius(radius);
+                if let Some(phase_space) = phase_space {
+                    node = node.with_phase_space(parse_phase_space(&phase_space)?);
+                }
+                if let Some(tolerance) = tolerance {
+                    node = node.interpolated(tolerance);
+                }
+                Ok(node)
+            }
+        }
+
+        #[pyfunction]
+        #[pyo3(name = $name, signature = (name, channel, tolerance=None, phase_space=None, radius=1.0))]
+        fn $function(
+            name: &str,
+            channel: usize,
+            tolerance: Option<f64>,
+            phase_space: Option<Vec<String>>,
+            radius: f64,
+        ) -> PyResult<PyAmpOp> {
+            let node = $kmatrix::from_py(channel, tolerance, phase_space, radius)?;
+            Ok(Amplitude::new(name, Box::new(node)).into())
+        }
+
+        #[pyfunction]
+        #[pyo3(name = $gradient_name, signature = (channel, tolerance=None, phase_space=None, radius=1.0))]
+        fn $gradient_function(
+            channel: usize,
+            tolerance: Option<f64>,
+            phase_space: Option<Vec<String>>,
+            radius: f64,
+        ) -> PyResult<PyGradient> {
+            Ok(PyGradient::new($kmatrix::from_py(
+                channel,
+                tolerance,
+                phase_space,
+                radius,
+            )?))
+        }
+    };
+}
+
+kmatrix_pyfunction!(
+    kmatrix_a0,
+    kmatrix_a0_gradient,
+    "KMatrixA0",
+    "KMatrixA0Gradient",
+    KMatrixA0
+);
+kmatrix_pyfunction!(
+    kmatrix_a2,
+    kmatrix_a2_gradient,
+    "KMatrixA2",
+    "KMatrixA2Gradient",
+    KMatrixA2
+);
+kmatrix_pyfunction!(
+    kmatrix_f0,
+    kmatrix_f0_gradient,
+    "KMatrixF0",
+    "KMatrixF0Gradient",
+    KMatrixF0
+);
+kmatrix_pyfunction!(
+    kmatrix_f2,
+    kmatrix_f2_gradient,
+    "KMatrixF2",
+    "KMatrixF2Gradient",
+    KMatrixF2
+);
+kmatrix_pyfunction!(
+    kmatrix_k0star,
+    kmatrix_k0star_gradient,
+    "KMatrixK0Star",
+    "KMatrixK0StarGradient",
+    KMatrixK0Star
+);
+kmatrix_pyfunction!(
+    kmatrix_kstar,
+    kmatrix_kstar_gradient,
+    "KMatrixKStar",
+    "KMatrixKStarGradient",
+    KMatrixKStar
+);
+kmatrix_pyfunction!(
+    kmatrix_pi1,
+    kmatrix_pi1_gradient,
+    "KMatrixPi1",
+    "KMatrixPi1Gradient",
+    KMatrixPi1
+);
+kmatrix_pyfunction!(
+    kmatrix_rho,
+    kmatrix_rho_gradient,
+    "KMatrixRho",
+    "KMatrixRhoGradient",
+    KMatrixRho
+);
+
 pub fn pyo3_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(breit_wigner, m)?)?;
+    m.add_function(wrap_pyfunction!(breit_wigner_gradient, m)?)?;
     m.add_function(wrap_pyfunction!(kmatrix_a0, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_a0_gradient, m)?)?;
     m.add_function(wrap_pyfunction!(kmatrix_a2, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_a2_gradient, m)?)?;
     m.add_function(wrap_pyfunction!(kmatrix_f0, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_f0_gradient, m)?)?;
     m.add_function(wrap_pyfunction!(kmatrix_f2, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_f2_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_k0star, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_k0star_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_kstar, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_kstar_gradient, m)?)?;
     m.add_function(wrap_pyfunction!(kmatrix_pi1, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_pi1_gradient, m)?)?;
     m.add_function(wrap_pyfunction!(kmatrix_rho, m)?)?;
+    m.add_function(wrap_pyfunction!(kmatrix_rho_gradient, m)?)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Constants = KMatrixConstants<1, 1>;
+
+    #[test]
+    fn chew_mandelstam_matches_rho_convention() {
+        for (m1, m2) in [(0.13957, 0.13957), (0.13957, 0.493677), (0.493677, 0.13957)] {
+            let threshold = (m1 + m2) * (m1 + m2);
+            let pseudo_threshold = (m1 - m2) * (m1 - m2);
+            for s in [1.01, 1.5, 4.0, 10.0].map(|x| x * threshold) {
+                let c = Constants::chew_mandelstam(s, m1, m2);
+                assert!((c.im + Constants::rho(s, m1, m2).re).abs() < 1e-12);
+            }
+            for s in
+                [0.1, 0.5, 0.9, 0.99].map(|x| pseudo_threshold + x * (threshold - pseudo_threshold))
+            {
+                assert_eq!(Constants::chew_mandelstam(s, m1, m2).im, 0.0);
+            }
+            for s in [threshold, pseudo_threshold]
+                .into_iter()
+                .filter(|&s| s > 0.0)
+            {
+                let below = Constants::chew_mandelstam(s * (1.0 - 1e-9), m1, m2);
+                let above = Constants::chew_mandelstam(s * (1.0 + 1e-9), m1, m2);
+                assert!((below - above).norm() < 1e-3);
+            }
+        }
+    }
+}
@@ -1,27 +1,345 @@
+use nalgebra::{DMatrix, DVector, SMatrix, SVector, Vector3};
 use pyo3::prelude::*;
 use rayon::prelude::*;
 use rustitude_core::prelude::*;
 use sphrs::SHCoordinates;
-use std::f64::consts::PI;
+use std::{
+    collections::HashMap,
+    f64::consts::PI,
+    sync::{Arc, OnceLock, RwLock},
+};
 
-use crate::utils::Frame;
+use crate::{
+    errors::GluexError,
+    polarization::{CircularPolarization, Polarization, PyCircularPolarization, PyPolarization},
+    utils::{
+        breakup_momentum, small_wigner_d_matrix, sqrt_abs_gradient, wigner_d_matrix, Frame,
+        Gradient, PyGradient,
+    },
+};
+
+// The SDME intensity is linear in the SDMEs, W = w_0 + Σ ρ_k ∂W/∂ρ_k, so this returns w_0 and
+// the derivatives in the order of `parameters()`.
+fn sdme_terms(data: &(f64, f64, f64, f64, f64, f64, f64), circular: bool) -> (f64, Vec<f64>) {
+    let (costheta, sinsqtheta, sin2theta, phi, r_big_phi, pgamma, pcirc) = *data;
+    let k = 3.0 / (4.0 * PI);
+    let mut terms = vec![
+        k * (1.5 * costheta * costheta - 0.5),
+        -k * f64::sqrt(2.0) * sin2theta * f64::cos(phi),
+        -k * sinsqtheta * f64::cos(2.0 * phi),
+        -k * pgamma * f64::cos(2.0 * r_big_phi) * sinsqtheta,
+        -k * pgamma * f64::cos(2.0 * r_big_phi) * costheta * costheta,
+        k * pgamma * f64::cos(2.0 * r_big_phi) * f64::sqrt(2.0) * sin2theta * f64::cos(phi),
+        k * pgamma * f64::cos(2.0 * r_big_phi) * sinsqtheta * f64::cos(2.0 * phi),
+        -k * pgamma * f64::sin(2.0 * r_big_phi) * f64::sqrt(2.0) * sin2theta * f64::sin(phi),
+        -k * pgamma * f64::sin(2.0 * r_big_phi) * sinsqtheta * f64::sin(2.0 * phi),
+    ];
+    if circular {
+        terms.push(pcirc * k * f64::sqrt(2.0) * sin2theta * f64::sin(phi));
+        terms.push(pcirc * k * sinsqtheta * f64::sin(2.0 * phi));
+    }
+    (k * 0.5 * sinsqtheta, terms)
+}
+
+const SDME_NAMES: [&str; 11] = [
+    "rho_000", "rho_100", "rho_1n10", "rho_111", "rho_001", "rho_101", "rho_1n11", "rho_102",
+    "rho_1n12", "rho_103", "rho_1n13",
+];
+
+// (α, 2λ, 2λ', imaginary part) of each element in `SDME_NAMES`
+const SDME_ELEMENTS: [(usize, isize, isize, bool); 11] = [
+    (0, 0, 0, false),
+    (0, 2, 0, false),
+    (0, 2, -2, false),
+    (1, 2, 2, false),
+    (1, 0, 0, false),
+    (1, 2, 0, false),
+    (1, 2, -2, false),
+    (2, 2, 0, true),
+    (2, 2, -2, true),
+    (3, 2, 0, true),
+    (3, 2, -2, true),
+];
+
+// Production amplitudes T_{λ_V λ_N', λ_γ λ_N} with λ_γ = +1, ordered by λ_V = 1, 0, -1 and then
+// by λ_N' and λ_N (+ before -). Parity fixes the rest through
+//   T_{λ_V λ_N', -1 λ_N} = (-1)^{(-λ_V + λ_N') - (1 + λ_N)} T_{-λ_V -λ_N', +1 -λ_N},
+// and the SDMEs follow Schilling et al. (Nucl. Phys. B 15, 397), e.g.
+//   ρ¹_{λλ'} = Σ T_{λ λ_N', -λ_γ λ_N} T*_{λ' λ_N', λ_γ λ_N} / Σ |T|²,
+// so every set of amplitudes gives a positive intensity. The SDMEs don't depend on the overall
+// scale or phase, so t_1pp = 1 is the reference and the other 11 amplitudes are fit relative to it.
+// A fit therefore can't reach t_1pp = 0: if the true t_1pp is small, the other amplitudes grow
+// without bound instead.
+const N_AMPLITUDES: usize = 12;
+const N_AMPLITUDE_PARAMETERS: usize = 2 * (N_AMPLITUDES - 1);
+type AmplitudeMatrix = SMatrix<Complex64, N_AMPLITUDES, N_AMPLITUDES>;
+
+fn amplitude_index(
+    two_lambda_v: isize,
+    two_lambda_out: isize,
+    two_lambda_gamma: isize,
+    two_lambda_in: isize,
+) -> (f64, usize) {
+    if two_lambda_gamma < 0 {
+        let (sign, index) = amplitude_index(-two_lambda_v, -two_lambda_out, 2, -two_lambda_in);
+        let exponent = ((-two_lambda_v + two_lambda_out) - (2 + two_lambda_in)) / 2;
+        return (
+            if exponent.rem_euclid(2) == 0 {
+                sign
+            } else {
+                -sign
+            },
+            index,
+        );
+    }
+    (
+        1.0,
+        4 * ((2 - two_lambda_v) / 2) as usize
+            + 2 * usize::from(two_lambda_out < 0)
+            + usize::from(two_lambda_in < 0),
+    )
+}
+
+fn amplitude_names() -> Vec<String> {
+    ["1", "0", "n1"]
+        .iter()
+        .flat_map(|v| {
+            ["pp", "pm", "mp", "mm"]
+                .iter()
+                .flat_map(move |n| [format!("t_{}{}_re", v, n), format!("t_{}{}_im", v, n)])
+        })
+        .skip(2)
+        .collect()
+}
+
+// Each SDME numerator as a† H a for the amplitudes a.
+fn sdme_matrices() -> &'static [AmplitudeMatrix; 11] {
+    static MATRICES: OnceLock<[AmplitudeMatrix; 11]> = OnceLock::new();
+    MATRICES.get_or_init(|| {
+        SDME_ELEMENTS.map(|(alpha, two_lambda, two_lambda_prime, _)| {
+            let mut h = AmplitudeMatrix::zeros();
+            for two_lambda_gamma in [2, -2] {
+                let (two_lambda_gamma_left, c) = match alpha {
+                    0 => (two_lambda_gamma, Complex64::from(1.0)),
+                    1 => (-two_lambda_gamma, Complex64::from(1.0)),
+                    2 => (
+                        -two_lambda_gamma,
+                        Complex64::new(0.0, (two_lambda_gamma / 2) as f64),
+                    ),
+                    _ => (
+                        two_lambda_gamma,
+                        Complex64::from((two_lambda_gamma / 2) as f64),
+                    ),
+                };
+                for two_lambda_out in [1, -1] {
+                    for two_lambda_in in [1, -1] {
+                        let (sign_u, u) = amplitude_index(
+                            two_lambda,
+                            two_lambda_out,
+                            two_lambda_gamma_left,
+                            two_lambda_in,
+                        );
+                        let (sign_v, v) = amplitude_index(
+                            two_lambda_prime,
+                            two_lambda_out,
+                            two_lambda_gamma,
+                            two_lambda_in,
+                        );
+                        h[(v, u)] += c * sign_u * sign_v;
+                    }
+                }
+            }
+            h
+        })
+    })
+}
+
+fn amplitude_vector(parameters: &[f64]) -> Result<SVector<Complex64, N_AMPLITUDES>, GluexError> {
+    if parameters.len() != N_AMPLITUDE_PARAMETERS {
+        return Err(GluexError::InvalidParameterCount {
+            expected: N_AMPLITUDE_PARAMETERS,
+            found: parameters.len(),
+        });
+    }
+    Ok(SVector::from_fn(|i, _| {
+        if i == 0 {
+            Complex64::from(1.0)
+        } else {
+            Complex64::new(parameters[2 * i - 2], parameters[2 * i - 1])
+        }
+    }))
+}
+
+// The SDMEs (in the order of `SDME_NAMES`) built from production amplitudes.
+pub fn sdmes_from_amplitudes(parameters: &[f64]) -> Result<Vec<f64>, GluexError> {
+    let a = amplitude_vector(parameters)?;
+    let norm = 2.0 * a.norm_squared();
+    Ok(sdme_matrices()
+        .iter()
+        .zip(SDME_ELEMENTS)
+        .map(|(h, (_, _, _, imaginary))| {
+            let f = a.dotc(&(h * a));
+            if imaginary {
+                f.im / norm
+            } else {
+                f.re / norm
+            }
+        })
+        .collect())
+}
+
+// The SDMEs along with their derivatives with respect to each amplitude parameter.
+pub fn sdme_jacobian(parameters: &[f64]) -> Result<(Vec<f64>, Vec<Vec<f64>>), GluexError> {
+    let a = amplitude_vector(parameters)?;
+    let norm = 2.0 * a.norm_squared();
+    Ok(sdme_matrices()
+        .iter()
+        .zip(SDME_ELEMENTS)
+        .map(|(h, (_, _, _, imaginary))| {
+            let part = |z: Complex64| if imaginary { z.im } else { z.re };
+            let ha = h * a;
+            let hta = h.transpose() * a.conjugate();
+            let rho = part(a.dotc(&ha)) / norm;
+            let derivatives = (1..N_AMPLITUDES)
+                .flat_map(|x| {
+                    [
+                        (part(ha[x] + hta[x]) - rho * 4.0 * a[x].re) / norm,
+                        (part(Complex64::i() * (hta[x] - ha[x])) - rho * 4.0 * a[x].im) / norm,
+                    ]
+                })
+                .collect();
+            (rho, derivatives)
+        })
+        .unzip())
+}
+
+// The SDMEs and their covariance J C Jᵀ, given the covariance C of the amplitude parameters. The 22
+// parameters still map onto 11 SDMEs, so the fit Hessian keeps flat directions; C should be its
+// pseudo-inverse, which J projects out.
+pub fn sdme_covariance(
+    parameters: &[f64],
+    covariance: &DMatrix<f64>,
+) -> Result<(DVector<f64>, DMatrix<f64>), GluexError> {
+    for found in [covariance.nrows(), covariance.ncols()] {
+        if found != N_AMPLITUDE_PARAMETERS {
+            return Err(GluexError::InvalidParameterCount {
+                expected: N_AMPLITUDE_PARAMETERS,
+                found,
+            });
+        }
+    }
+    let (rho, rows) = sdme_jacobian(parameters)?;
+    let jacobian = DMatrix::from_fn(rows.len(), N_AMPLITUDE_PARAMETERS, |i, k| rows[i][k]);
+    Ok((
+        DVector::from_vec(rho),
+        &jacobian * covariance * jacobian.transpose(),
+    ))
+}
+
+type SDMEJacobian = (Vec<f64>, Vec<Vec<f64>>);
+
+// The SDMEs and their Jacobian for the last amplitude parameters seen, so that a pass over the
+// dataset builds them once rather than once per event.
+#[derive(Default)]
+struct SDMECache(RwLock<Option<(Vec<f64>, Arc<SDMEJacobian>)>>);
+
+impl SDMECache {
+    fn get(&self, parameters: &[f64]) -> Result<Arc<SDMEJacobian>, GluexError> {
+        if let Some((cached, sdmes)) = self
+            .0
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .as_ref()
+        {
+            if cached.as_slice() == parameters {
+                return Ok(Arc::clone(sdmes));
+            }
+        }
+        let sdmes = Arc::new(sdme_jacobian(parameters)?);
+        *self
+            .0
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) =
+            Some((parameters.to_vec(), Arc::clone(&sdmes)));
+        Ok(sdmes)
+    }
+}
+
+fn sdme_parameters(circular: bool, amplitudes: bool) -> Vec<String> {
+    if amplitudes {
+        return amplitude_names();
+    }
+    SDME_NAMES[..if circular { 11 } else { 9 }]
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+fn sdme_gradient(
+    data: &(f64, f64, f64, f64, f64, f64, f64),
+    circular: bool,
+    amplitudes: bool,
+    cache: &SDMECache,
+    parameters: &[f64],
+) -> Result<Vec<Complex64>, GluexError> {
+    let (w0, dw) = sdme_terms(data, circular);
+    if !amplitudes {
+        let w = w0 + dw.iter().zip(parameters).map(|(d, p)| d * p).sum::<f64>();
+        return Ok(sqrt_abs_gradient(w, &dw));
+    }
+    let sdmes = cache.get(parameters)?;
+    let (rho, jacobian) = &*sdmes;
+    let w = w0 + dw.iter().zip(rho).map(|(d, r)| d * r).sum::<f64>();
+    let dw_dp: Vec<f64> = (0..parameters.len())
+        .map(|p| dw.iter().zip(jacobian).map(|(d, row)| d * row[p]).sum())
+        .collect();
+    Ok(sqrt_abs_gradient(w, &dw_dp))
+}
 
 pub struct TwoPiSDME {
     frame: Frame,
-    data: Vec<(f64, f64, f64, f64, f64, f64)>,
+    polarization: Polarization,
+    circular: Option<CircularPolarization>,
+    amplitudes: bool,
+    cache: SDMECache,
+    data: Vec<(f64, f64, f64, f64, f64, f64, f64)>,
 }
 
 impl TwoPiSDME {
     pub fn new(frame: Frame) -> Self {
         Self {
             frame,
+            polarization: Polarization::default(),
+            circular: None,
+            amplitudes: false,
+            cache: SDMECache::default(),
             data: Vec::default(),
         }
     }
+
+    pub fn with_polarization(mut self, polarization: Polarization) -> Self {
+        self.polarization = polarization;
+        self
+    }
+
+    pub fn with_circular_polarization(mut self, circular: CircularPolarization) -> Self {
+        self.circular = Some(circular);
+        self
+    }
+
+    // Fit production amplitudes instead of the SDMEs themselves (see `sdmes_from_amplitudes`).
+    pub fn with_production_amplitudes(mut self) -> Self {
+        self.amplitudes = true;
+        self
+    }
 }
 
 impl Node for TwoPiSDME {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.polarization.check_dataset(dataset)?;
+        if let Some(circular) = &self.circular {
+            circular.check_dataset(dataset)?;
+        }
         self.data = dataset
             .events
             .read()
@@ -30,90 +348,135 @@ impl Node for TwoPiSDME {
                 let resonance = event.daughter_p4s[0] + event.daughter_p4s[1];
                 let daughter_res_vec = event.daughter_p4s[0].boost_along(&resonance).momentum();
                 let (_, y, _, p) = self.frame.coordinates(&resonance, &daughter_res_vec, event);
-                let big_phi = y.dot(&event.eps).atan2(
-                    event
-                        .beam_p4
-                        .momentum()
-                        .normalize()
-                        .dot(&event.eps.cross(&y)),
-                );
-                let pgamma = event.eps.norm();
-                (
+                let (big_phi, pgamma) = self.polarization.big_phi(&y, event)?;
+                let pcirc = match &self.circular {
+                    Some(circular) => circular.value(event)?,
+                    None => 0.0,
+                };
+                Ok((
                     p.theta_cos(),
-                    p.theta().powi(2),
+                    p.theta().sin().powi(2),
                     f64::sin(2.0 * p.theta()),
                     p.phi(),
                     big_phi,
                     pgamma,
-                )
+                    pcirc,
+                ))
             })
-            .collect();
+            .collect::<Result<Vec<_>, GluexError>>()?;
         Ok(())
     }
 
     fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
-        let (costheta, sinsqtheta, sin2theta, phi, big_phi, pgamma) = self.data[event.index];
-        let pol_angle = event.eps[0].acos();
-        let r_big_phi = pol_angle * 0.017453293 + big_phi;
-        let rho_000 = parameters[0];
-        let rho_100 = parameters[1];
-        let rho_1n10 = parameters[2];
-        let rho_111 = parameters[3];
-        let rho_001 = parameters[4];
-        let rho_101 = parameters[5];
-        let rho_1n11 = parameters[6];
-        let rho_102 = parameters[7];
-        let rho_1n12 = parameters[8];
+        let (costheta, sinsqtheta, sin2theta, phi, r_big_phi, pgamma, pcirc) =
+            self.data[event.index];
+        let amplitude_sdmes = if self.amplitudes {
+            Some(self.cache.get(parameters)?)
+        } else {
+            None
+        };
+        let rho = amplitude_sdmes
+            .as_ref()
+            .map_or(parameters, |sdmes| &sdmes.0);
+        let rho_000 = rho[0];
+        let rho_100 = rho[1];
+        let rho_1n10 = rho[2];
+        let rho_111 = rho[3];
+        let rho_001 = rho[4];
+        let rho_101 = rho[5];
+        let rho_1n11 = rho[6];
+        let rho_102 = rho[7];
+        let rho_1n12 = rho[8];
+        let w3 = if self.circular.is_some() {
+            let rho_103 = rho[9];
+            let rho_1n13 = rho[10];
+            (3.0 / (4.0 * PI))
+                * (f64::sqrt(2.0) * rho_103 * sin2theta * f64::sin(phi)
+                    + rho_1n13 * sinsqtheta * f64::sin(2.0 * phi))
+        } else {
+            0.0
+        };
 
         Ok(f64::sqrt(f64::abs(
             (3.0 / (4.0 * PI))
                 * (0.5 * (1.0 - rho_000) + 0.5 * (3.0 * rho_000 - 1.0) * costheta * costheta
                     - f64::sqrt(2.0) * rho_100 * sin2theta * f64::cos(phi)
-                    - rho_1n10 * f64::cos(2.0 * phi))
-                - pgamma
-                    * f64::cos(2.0 * r_big_phi)
-                    * (rho_111 * sinsqtheta + rho_001 * costheta * costheta
-                        - f64::sqrt(2.0) * rho_101 * sin2theta * f64::cos(phi)
-                        - rho_1n11 * sinsqtheta * f64::cos(2.0 * phi))
-                - pgamma
-                    * f64::sin(2.0 * r_big_phi)
-                    * (f64::sqrt(2.0) * rho_102 * sin2theta * f64::sin(phi)
-                        + rho_1n12 * sinsqtheta * f64::sin(2.0 * phi)),
+                    - rho_1n10 * sinsqtheta * f64::cos(2.0 * phi)
+                    - pgamma
+                        * f64::cos(2.0 * r_big_phi)
+                        * (rho_111 * sinsqtheta + rho_001 * costheta * costheta
+                            - f64::sqrt(2.0) * rho_101 * sin2theta * f64::cos(phi)
+                            - rho_1n11 * sinsqtheta * f64::cos(2.0 * phi))
+                    - pgamma
+                        * f64::sin(2.0 * r_big_phi)
+                        * (f64::sqrt(2.0) * rho_102 * sin2theta * f64::sin(phi)
+                            + rho_1n12 * sinsqtheta * f64::sin(2.0 * phi)))
+                + pcirc * w3,
         ))
         .into())
     }
 
     fn parameters(&self) -> Vec<String> {
-        vec![
-            "rho_000".to_string(),
-            "rho_100".to_string(),
-            "rho_1n10".to_string(),
-            "rho_111".to_string(),
-            "rho_001".to_string(),
-            "rho_101".to_string(),
-            "rho_1n11".to_string(),
-            "rho_102".to_string(),
-            "rho_1n12".to_string(),
-        ]
+        sdme_parameters(self.circular.is_some(), self.amplitudes)
+    }
+}
+
+impl Gradient for TwoPiSDME {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(sdme_gradient(
+            &self.data[event.index],
+            self.circular.is_some(),
+            self.amplitudes,
+            &self.cache,
+            parameters,
+        )?)
     }
 }
 
 pub struct ThreePiSDME {
     frame: Frame,
-    data: Vec<(f64, f64, f64, f64, f64, f64)>,
+    polarization: Polarization,
+    circular: Option<CircularPolarization>,
+    amplitudes: bool,
+    cache: SDMECache,
+    data: Vec<(f64, f64, f64, f64, f64, f64, f64)>,
 }
 
 impl ThreePiSDME {
     pub fn new(frame: Frame) -> Self {
         Self {
             frame,
+            polarization: Polarization::default(),
+            circular: None,
+            amplitudes: false,
+            cache: SDMECache::default(),
             data: Vec::default(),
         }
     }
+
+    pub fn with_polarization(mut self, polarization: Polarization) -> Self {
+        self.polarization = polarization;
+        self
+    }
+
+    pub fn with_circular_polarization(mut self, circular: CircularPolarization) -> Self {
+        self.circular = Some(circular);
+        self
+    }
+
+    // Fit production amplitudes instead of the SDMEs themselves (see `sdmes_from_amplitudes`).
+    pub fn with_production_amplitudes(mut self) -> Self {
+        self.amplitudes = true;
+        self
+    }
 }
 
 impl Node for ThreePiSDME {
     fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.polarization.check_dataset(dataset)?;
+        if let Some(circular) = &self.circular {
+            circular.check_dataset(dataset)?;
+        }
         self.data = dataset
             .events
             .read()
@@ -125,99 +488,712 @@ impl Node for ThreePiSDME {
                 let p2_res_vec = event.daughter_p4s[1].boost_along(&resonance).momentum();
                 let daughter_res_vec = p1_res_vec.cross(&p2_res_vec).normalize();
                 let (_, y, _, p) = self.frame.coordinates(&resonance, &daughter_res_vec, event);
-                let big_phi = y.dot(&event.eps).atan2(
-                    event
-                        .beam_p4
-                        .momentum()
-                        .normalize()
-                        .dot(&event.eps.cross(&y)),
-                );
-                let pgamma = event.eps.norm();
-                (
+                let (big_phi, pgamma) = self.polarization.big_phi(&y, event)?;
+                let pcirc = match &self.circular {
+                    Some(circular) => circular.value(event)?,
+                    None => 0.0,
+                };
+                Ok((
                     p.theta_cos(),
-                    p.theta().powi(2),
+                    p.theta().sin().powi(2),
                     f64::sin(2.0 * p.theta()),
                     p.phi(),
                     big_phi,
                     pgamma,
-                )
+                    pcirc,
+                ))
             })
-            .collect();
+            .collect::<Result<Vec<_>, GluexError>>()?;
         Ok(())
     }
 
     fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
-        let (costheta, sinsqtheta, sin2theta, phi, big_phi, pgamma) = self.data[event.index];
-        let pol_angle = event.eps[0].acos();
-        let r_big_phi = pol_angle * 0.017453293 + big_phi;
-        let rho_000 = parameters[0];
-        let rho_100 = parameters[1];
-        let rho_1n10 = parameters[2];
-        let rho_111 = parameters[3];
-        let rho_001 = parameters[4];
-        let rho_101 = parameters[5];
-        let rho_1n11 = parameters[6];
-        let rho_102 = parameters[7];
-        let rho_1n12 = parameters[8];
+        let (costheta, sinsqtheta, sin2theta, phi, r_big_phi, pgamma, pcirc) =
+            self.data[event.index];
+        let amplitude_sdmes = if self.amplitudes {
+            Some(self.cache.get(parameters)?)
+        } else {
+            None
+        };
+        let rho = amplitude_sdmes
+            .as_ref()
+            .map_or(parameters, |sdmes| &sdmes.0);
+        let rho_000 = rho[0];
+        let rho_100 = rho[1];
+        let rho_1n10 = rho[2];
+        let rho_111 = rho[3];
+        let rho_001 = rho[4];
+        let rho_101 = rho[5];
+        let rho_1n11 = rho[6];
+        let rho_102 = rho[7];
+        let rho_1n12 = rho[8];
+        let w3 = if self.circular.is_some() {
+            let rho_103 = rho[9];
+            let rho_1n13 = rho[10];
+            (3.0 / (4.0 * PI))
+                * (f64::sqrt(2.0) * rho_103 * sin2theta * f64::sin(phi)
+                    + rho_1n13 * sinsqtheta * f64::sin(2.0 * phi))
+        } else {
+            0.0
+        };
 
         Ok(f64::sqrt(f64::abs(
             (3.0 / (4.0 * PI))
                 * (0.5 * (1.0 - rho_000) + 0.5 * (3.0 * rho_000 - 1.0) * costheta * costheta
                     - f64::sqrt(2.0) * rho_100 * sin2theta * f64::cos(phi)
-                    - rho_1n10 * f64::cos(2.0 * phi))
-                - pgamma
-                    * f64::cos(2.0 * r_big_phi)
-                    * (rho_111 * sinsqtheta + rho_001 * costheta * costheta
-                        - f64::sqrt(2.0) * rho_101 * sin2theta * f64::cos(phi)
-                        - rho_1n11 * sinsqtheta * f64::cos(2.0 * phi))
-                - pgamma
-                    * f64::sin(2.0 * r_big_phi)
-                    * (f64::sqrt(2.0) * rho_102 * sin2theta * f64::sin(phi)
-                        + rho_1n12 * sinsqtheta * f64::sin(2.0 * phi)),
+                    - rho_1n10 * sinsqtheta * f64::cos(2.0 * phi)
+                    - pgamma
+                        * f64::cos(2.0 * r_big_phi)
+                        * (rho_111 * sinsqtheta + rho_001 * costheta * costheta
+                            - f64::sqrt(2.0) * rho_101 * sin2theta * f64::cos(phi)
+                            - rho_1n11 * sinsqtheta * f64::cos(2.0 * phi))
+                    - pgamma
+                        * f64::sin(2.0 * r_big_phi)
+                        * (f64::sqrt(2.0) * rho_102 * sin2theta * f64::sin(phi)
+                            + rho_1n12 * sinsqtheta * f64::sin(2.0 * phi)))
+                + pcirc * w3,
         ))
         .into())
     }
 
     fn parameters(&self) -> Vec<String> {
-        vec![
-            "rho_000".to_string(),
-            "rho_100".to_string(),
-            "rho_1n10".to_string(),
-            "rho_111".to_string(),
-            "rho_001".to_string(),
-            "rho_101".to_string(),
-            "rho_1n11".to_string(),
-            "rho_102".to_string(),
-            "rho_1n12".to_string(),
-        ]
+        sdme_parameters(self.circular.is_some(), self.amplitudes)
+    }
+}
+
+impl Gradient for ThreePiSDME {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        Ok(sdme_gradient(
+            &self.data[event.index],
+            self.circular.is_some(),
+            self.amplitudes,
+            &self.cache,
+            parameters,
+        )?)
+    }
+}
+
+// The independent SDMEs of a spin-J state decaying to two spinless particles are Re ρ⁰_{λλ'},
+// Re ρ¹_{λλ'} and Im ρ²_{λλ'} with λ ≥ |λ'|, where
+//   W(Ω, Φ) = W⁰(Ω) - P_γ cos(2Φ) W¹(Ω) - P_γ sin(2Φ) W²(Ω),
+//   W^α(Ω) = (2J+1)/4π Σ D^J*_{λ0}(φ, θ, 0) ρ^α_{λλ'} D^J_{λ'0}(φ, θ, 0).
+// The rest follow from hermiticity and parity, ρ^α_{-λ-λ'} = ±(-1)^{λ-λ'} ρ^α_{λλ'} (- for α = 2),
+// and ρ⁰_{JJ} is fixed by the trace, so J = 1 reproduces the elements of `TwoPiSDME`.
+fn spin_sdme_alpha_elements(j: usize, alpha: usize) -> impl Iterator<Item = (usize, isize, isize)> {
+    let j = j as isize;
+    (0..=j).flat_map(move |lambda| {
+        (-lambda..=lambda)
+            .rev()
+            .filter(move |&lambda_prime| match alpha {
+                0 => !(lambda == j && lambda_prime == j),
+                1 => true,
+                _ => lambda != lambda_prime,
+            })
+            .map(move |lambda_prime| (alpha, lambda, lambda_prime))
+    })
+}
+
+fn spin_sdme_elements(j: usize) -> Vec<(usize, isize, isize)> {
+    (0..3)
+        .flat_map(|alpha| spin_sdme_alpha_elements(j, alpha))
+        .collect()
+}
+
+fn spin_sdme_name(alpha: usize, lambda: isize, lambda_prime: isize) -> String {
+    let label = |l: isize| {
+        if l < 0 {
+            format!("n{}", -l)
+        } else {
+            l.to_string()
+        }
+    };
+    format!("rho_{}{}{}", label(lambda), label(lambda_prime), alpha)
+}
+
+// The entries of ρ^α fixed by hermiticity and parity when one independent element is set to 1
+// (or i for α = 2, 3).
+fn spin_sdme_entries(
+    alpha: usize,
+    lambda: isize,
+    lambda_prime: isize,
+) -> Vec<(isize, isize, Complex64)> {
+    let value = if alpha >= 2 {
+        Complex64::i()
+    } else {
+        Complex64::from(1.0)
+    };
+    let parity = if alpha >= 2 { -1.0 } else { 1.0 }
+        * if (lambda - lambda_prime) % 2 == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+    let mut entries = vec![
+        (lambda, lambda_prime, value),
+        (lambda_prime, lambda, value.conj()),
+        (-lambda, -lambda_prime, value * parity),
+        (-lambda_prime, -lambda, value.conj() * parity),
+    ];
+    entries.sort_by_key(|entry| (entry.0, entry.1));
+    entries.dedup_by_key(|entry| (entry.0, entry.1));
+    entries
+}
+
+// W^α(Ω) for a single independent element
+fn spin_sdme_basis(
+    alpha: usize,
+    lambda: isize,
+    lambda_prime: isize,
+    d: &HashMap<isize, Complex64>,
+    norm: f64,
+) -> f64 {
+    norm * spin_sdme_entries(alpha, lambda, lambda_prime)
+        .iter()
+        .map(|(l, l_prime, v)| (d[l].conj() * v * d[l_prime]).re)
+        .sum::<f64>()
+}
+
+pub struct TwoBodySDME {
+    j: usize,
+    frame: Frame,
+    polarization: Polarization,
+    elements: Vec<(usize, isize, isize)>,
+    data: Vec<(f64, Vec<f64>)>,
+}
+
+impl TwoBodySDME {
+    pub fn new(j: usize, frame: Frame) -> Self {
+        Self {
+            j,
+            frame,
+            polarization: Polarization::default(),
+            elements: spin_sdme_elements(j),
+            data: Vec::default(),
+        }
+    }
+
+    pub fn with_polarization(mut self, polarization: Polarization) -> Self {
+        self.polarization = polarization;
+        self
+    }
+}
+
+impl Node for TwoBodySDME {
+    fn precalculate(&mut self, dataset: &Dataset) -> Result<(), NodeError> {
+        self.polarization.check_dataset(dataset)?;
+        let j = self.j as isize;
+        let norm = (2.0 * self.j as f64 + 1.0) / (4.0 * PI);
+        self.data = dataset
+            .events
+            .read()
+            .par_iter()
+            .map(|event| {
+                let resonance = event.daughter_p4s[0] + event.daughter_p4s[1];
+                let daughter_res_vec = event.daughter_p4s[0].boost_along(&resonance).momentum();
+                let (_, y, _, p) = self.frame.coordinates(&resonance, &daughter_res_vec, event);
+                let (big_phi, pgamma) = self.polarization.big_phi(&y, event)?;
+                let d = (-j..=j)
+                    .map(|lambda| {
+                        Ok((
+                            lambda,
+                            wigner_d_matrix(p.phi(), p.theta(), 0.0, 2 * self.j, 2 * lambda, 0)?,
+                        ))
+                    })
+                    .collect::<Result<HashMap<isize, Complex64>, GluexError>>()?;
+                // ρ⁰_{JJ} = (1 - ρ⁰_{00} - 2 Σ_{0<λ<J} ρ⁰_{λλ}) / 2, or ρ⁰_{00} = 1 for J = 0
+                let w_jj = spin_sdme_basis(0, j, j, &d, norm);
+                let w_0 = if j == 0 { w_jj } else { 0.5 * w_jj };
+                let terms = self
+                    .elements
+                    .iter()
+                    .map(|&(alpha, lambda, lambda_prime)| {
+                        let w = spin_sdme_basis(alpha, lambda, lambda_prime, &d, norm);
+                        match alpha {
+                            0 if lambda == lambda_prime && lambda == 0 => w - 0.5 * w_jj,
+                            0 if lambda == lambda_prime => w - w_jj,
+                            0 => w,
+                            1 => -pgamma * f64::cos(2.0 * big_phi) * w,
+                            _ => -pgamma * f64::sin(2.0 * big_phi) * w,
+                        }
+                    })
+                    .collect();
+                Ok((w_0, terms))
+            })
+            .collect::<Result<Vec<_>, GluexError>>()?;
+        Ok(())
+    }
+
+    fn calculate(&self, parameters: &[f64], event: &Event) -> Result<Complex64, NodeError> {
+        let (w0, terms) = &self.data[event.index];
+        let w = w0
+            + terms
+                .iter()
+                .zip(parameters)
+                .map(|(t, p)| t * p)
+                .sum::<f64>();
+        Ok(f64::sqrt(f64::abs(w)).into())
+    }
+
+    fn parameters(&self) -> Vec<String> {
+        self.elements
+            .iter()
+            .map(|&(alpha, lambda, lambda_prime)| spin_sdme_name(alpha, lambda, lambda_prime))
+            .collect()
+    }
+}
+
+impl Gradient for TwoBodySDME {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError> {
+        let (w0, terms) = &self.data[event.index];
+        let w = w0
+            + terms
+                .iter()
+                .zip(parameters)
+                .map(|(t, p)| t * p)
+                .sum::<f64>();
+        Ok(sqrt_abs_gradient(w, terms))
+    }
+}
+
+// Two-body production a + b -> c + d at fixed s and t, used to relate the frames of c.
+#[derive(Clone, Copy, Debug)]
+pub struct ProductionKinematics {
+    pub s: f64,
+    pub t: f64,
+    pub m_beam: f64,
+    pub m_target: f64,
+    pub m_meson: f64,
+    pub m_recoil: f64,
+}
+
+impl ProductionKinematics {
+    // The meson and an event in the lab (target rest) frame with the production plane in x-z.
+    fn event(&self) -> Result<(FourMomentum, Event), GluexError> {
+        let sqrt_s = self.s.sqrt();
+        let p_i = breakup_momentum(sqrt_s, self.m_beam, self.m_target);
+        let p_f = breakup_momentum(sqrt_s, self.m_meson, self.m_recoil);
+        let e_beam = f64::hypot(p_i, self.m_beam);
+        let e_meson = f64::hypot(p_f, self.m_meson);
+        let costheta = (self.t - self.m_beam.powi(2) - self.m_meson.powi(2)
+            + 2.0 * e_beam * e_meson)
+            / (2.0 * p_i * p_f);
+        if !(-1.0..=1.0).contains(&costheta) || sqrt_s < self.m_meson + self.m_recoil {
+            return Err(GluexError::UnphysicalKinematics {
+                s: self.s,
+                t: self.t,
+            });
+        }
+        let sintheta = f64::sqrt(1.0 - costheta.powi(2));
+        let target = FourMomentum::new(f64::hypot(p_i, self.m_target), 0.0, 0.0, -p_i);
+        let beam = FourMomentum::new(e_beam, 0.0, 0.0, p_i);
+        let meson = FourMomentum::new(e_meson, p_f * sintheta, 0.0, p_f * costheta);
+        let recoil = FourMomentum::new(
+            f64::hypot(p_f, self.m_recoil),
+            -p_f * sintheta,
+            0.0,
+            -p_f * costheta,
+        );
+        Ok((
+            meson.boost_along(&target),
+            Event {
+                index: 0,
+                weight: 1.0,
+                beam_p4: beam.boost_along(&target),
+                recoil_p4: recoil.boost_along(&target),
+                daughter_p4s: vec![meson.boost_along(&target)],
+                eps: Vector3::zeros(),
+            },
+        ))
+    }
+
+    // The angle of the rotation about the production-plane normal which takes the axes of `from`
+    // into those of `to`.
+    pub fn rotation_angle(&self, from: &Frame, to: &Frame) -> Result<f64, GluexError> {
+        for frame in [from, to] {
+            if let Frame::Canonical = frame {
+                return Err(GluexError::UnsupportedFrame("canonical".to_string()));
+            }
+        }
+        let (meson, event) = self.event()?;
+        let (_, y, z_from, _) = from.coordinates(&meson, &Vector3::z(), &event);
+        let (_, _, z_to, _) = to.coordinates(&meson, &Vector3::z(), &event);
+        Ok(y.dot(&z_from.cross(&z_to)).atan2(z_from.dot(&z_to)))
+    }
+}
+
+// Transforms spin-J SDMEs (named as in `TwoBodySDME`, plus rho_λλ'3 for circular polarization)
+// between the helicity, Gottfried-Jackson, CM-helicity and Adair frames, ρ^α → d(β)ᵀ ρ^α d(β).
+// Every independent element of each ρ^α that appears in `names` must be given, and the covariance
+// is propagated through the (linear) map.
+pub fn convert_sdmes(
+    j: usize,
+    names: &[String],
+    values: &DVector<f64>,
+    covariance: &DMatrix<f64>,
+    from: &Frame,
+    to: &Frame,
+    kinematics: &ProductionKinematics,
+) -> Result<(DVector<f64>, DMatrix<f64>), GluexError> {
+    let n = names.len();
+    for found in [values.len(), covariance.nrows(), covariance.ncols()] {
+        if found != n {
+            return Err(GluexError::InvalidParameterCount { expected: n, found });
+        }
+    }
+    let elements = names
+        .iter()
+        .map(|name| {
+            (0..4)
+                .flat_map(|alpha| spin_sdme_alpha_elements(j, alpha))
+                .find(|&(alpha, lambda, lambda_prime)| {
+                    spin_sdme_name(alpha, lambda, lambda_prime) == *name
+                })
+                .ok_or_else(|| GluexError::UnknownSDME(name.clone()))
+        })
+        .collect::<Result<Vec<_>, GluexError>>()?;
+    let mut alphas: Vec<usize> = elements.iter().map(|element| element.0).collect();
+    alphas.sort_unstable();
+    alphas.dedup();
+    for &alpha in &alphas {
+        if let Some((alpha, lambda, lambda_prime)) =
+            spin_sdme_alpha_elements(j, alpha).find(|element| !elements.contains(element))
+        {
+            return Err(GluexError::MissingSDME(spin_sdme_name(
+                alpha,
+                lambda,
+                lambda_prime,
+            )));
+        }
+    }
+    let beta = kinematics.rotation_angle(from, to)?;
+    let size = 2 * j + 1;
+    let offset = j as isize;
+    let index = |lambda: isize| (lambda + offset) as usize;
+    let mut d = DMatrix::<Complex64>::zeros(size, size);
+    for lambda in -offset..=offset {
+        for mu in -offset..=offset {
+            d[(index(lambda), index(mu))] =
+                small_wigner_d_matrix(beta, 2 * j, 2 * lambda, 2 * mu)?.into();
+        }
+    }
+    let transform = |x: &DVector<f64>| {
+        let mut rho = vec![DMatrix::<Complex64>::zeros(size, size); 4];
+        for (&(alpha, lambda, lambda_prime), value) in elements.iter().zip(x.iter()) {
+            for (l, l_prime, v) in spin_sdme_entries(alpha, lambda, lambda_prime) {
+                rho[alpha][(index(l), index(l_prime))] = v * *value;
+            }
+        }
+        // ρ⁰_{JJ} = ρ⁰_{-J-J} is fixed by the trace
+        let rest: Complex64 = (1 - offset..offset)
+            .map(|lambda| rho[0][(index(lambda), index(lambda))])
+            .sum();
+        let edge = (Complex64::from(1.0) - rest) / if j == 0 { 1.0 } else { 2.0 };
+        rho[0][(index(offset), index(offset))] = edge;
+        rho[0][(index(-offset), index(-offset))] = edge;
+        let rotated: Vec<DMatrix<Complex64>> = rho.iter().map(|r| d.transpose() * r * &d).collect();
+        DVector::from_iterator(
+            n,
+            elements.iter().map(|&(alpha, lambda, lambda_prime)| {
+                let v = rotated[alpha][(index(lambda), index(lambda_prime))];
+                if alpha >= 2 {
+                    v.im
+                } else {
+                    v.re
+                }
+            }),
+        )
+    };
+    let constant = transform(&DVector::zeros(n));
+    let mut jacobian = DMatrix::zeros(n, n);
+    for k in 0..n {
+        let mut unit = DVector::zeros(n);
+        unit[k] = 1.0;
+        jacobian.set_column(k, &(transform(&unit) - &constant));
+    }
+    Ok((
+        transform(values),
+        &jacobian * covariance * jacobian.transpose(),
+    ))
+}
+
+// Observables built from the spin-1 SDMEs (named as in `TwoPiSDME` or `TwoBodySDME`), each given
+// as (value, uncertainty):
+//   P_σ = 2ρ¹_{1-1} - ρ¹_{00},  σ^N/σ = (1 + P_σ)/2,  σ^U/σ = (1 - P_σ)/2,  Σ = 2ρ¹_{11} + ρ¹_{00}.
+#[derive(Clone, Copy, Debug)]
+pub struct SDMEObservables {
+    pub p_sigma: (f64, f64),
+    pub natural: (f64, f64),
+    pub unnatural: (f64, f64),
+    pub beam_asymmetry: (f64, f64),
+}
+
+pub fn sdme_observables(
+    names: &[String],
+    values: &[f64],
+    covariance: &DMatrix<f64>,
+) -> Result<SDMEObservables, GluexError> {
+    let n = names.len();
+    for found in [values.len(), covariance.nrows(), covariance.ncols()] {
+        if found != n {
+            return Err(GluexError::InvalidParameterCount { expected: n, found });
+        }
+    }
+    let position = |name: &str| {
+        names
+            .iter()
+            .position(|other| other == name)
+            .ok_or_else(|| GluexError::MissingSDME(name.to_string()))
+    };
+    let (rho_111, rho_001, rho_1n11) = (
+        position("rho_111")?,
+        position("rho_001")?,
+        position("rho_1n11")?,
+    );
+    let linear = |constant: f64, coefficients: &[(usize, f64)]| {
+        let mut gradient = DVector::zeros(n);
+        for (k, c) in coefficients {
+            gradient[*k] += c;
+        }
+        (
+            constant + gradient.dot(&DVector::from_column_slice(values)),
+            (gradient.transpose() * covariance * &gradient)[(0, 0)].sqrt(),
+        )
+    };
+    Ok(SDMEObservables {
+        p_sigma: linear(0.0, &[(rho_1n11, 2.0), (rho_001, -1.0)]),
+        natural: linear(0.5, &[(rho_1n11, 1.0), (rho_001, -0.5)]),
+        unnatural: linear(0.5, &[(rho_1n11, -1.0), (rho_001, 0.5)]),
+        beam_asymmetry: linear(0.0, &[(rho_111, 2.0), (rho_001, 1.0)]),
+    })
+}
+
+fn covariance_matrix(rows: Option<Vec<Vec<f64>>>, n: usize) -> Result<DMatrix<f64>, GluexError> {
+    match rows {
+        Some(rows) => {
+            if let Some(found) = std::iter::once(rows.len())
+                .chain(rows.iter().map(Vec::len))
+                .find(|&len| len != n)
+            {
+                return Err(GluexError::InvalidParameterCount { expected: n, found });
+            }
+            Ok(DMatrix::from_fn(n, n, |i, k| rows[i][k]))
+        }
+        None => Ok(DMatrix::zeros(n, n)),
+    }
+}
+
+fn py_two_pi_sdme_node(
+    frame: &str,
+    polarization: Option<PyPolarization>,
+    circular: Option<PyCircularPolarization>,
+    production_amplitudes: bool,
+) -> PyResult<TwoPiSDME> {
+    let mut sdme = TwoPiSDME::new(<Frame as std::str::FromStr>::from_str(frame)?)
+        .with_polarization(polarization.map(|p| p.0).unwrap_or_default());
+    if let Some(circular) = circular {
+        sdme = sdme.with_circular_polarization(circular.0);
+    }
+    if production_amplitudes {
+        sdme = sdme.with_production_amplitudes();
     }
+    Ok(sdme)
 }
 
 #[pyfunction]
-#[pyo3(name = "TwoPiSDME", signature = (name, frame="helicity"))]
-fn two_pi_sdme(name: &str, frame: &str) -> PyAmpOp {
-    Amplitude::new(
-        name,
-        Box::new(TwoPiSDME::new(
-            <Frame as std::str::FromStr>::from_str(frame).unwrap(),
-        )),
-    )
-    .into()
+#[pyo3(name = "TwoPiSDME", signature = (name, frame="helicity", polarization=None, circular=None, production_amplitudes=false))]
+fn two_pi_sdme(
+    name: &str,
+    frame: &str,
+    polarization: Option<PyPolarization>,
+    circular: Option<PyCircularPolarization>,
+    production_amplitudes: bool,
+) -> PyResult<PyAmpOp> {
+    let sdme = py_two_pi_sdme_node(frame, polarization, circular, production_amplitudes)?;
+    Ok(Amplitude::new(name, Box::new(sdme)).into())
 }
+
 #[pyfunction]
-#[pyo3(name = "ThreePiSDME", signature = (name, frame="helicity"))]
-fn three_pi_sdme(name: &str, frame: &str) -> PyAmpOp {
-    Amplitude::new(
-        name,
-        Box::new(ThreePiSDME::new(
-            <Frame as std::str::FromStr>::from_str(frame).unwrap(),
-        )),
-    )
-    .into()
+#[pyo3(name = "TwoPiSDMEGradient", signature = (frame="helicity", polarization=None, circular=None, production_amplitudes=false))]
+fn two_pi_sdme_gradient(
+    frame: &str,
+    polarization: Option<PyPolarization>,
+    circular: Option<PyCircularPolarization>,
+    production_amplitudes: bool,
+) -> PyResult<PyGradient> {
+    Ok(PyGradient::new(py_two_pi_sdme_node(
+        frame,
+        polarization,
+        circular,
+        production_amplitudes,
+    )?))
+}
+
+fn py_three_pi_sdme_node(
+    frame: &str,
+    polarization: Option<PyPolarization>,
+    circular: Option<PyCircularPolarization>,
+    production_amplitudes: bool,
+) -> PyResult<ThreePiSDME> {
+    let mut sdme = ThreePiSDME::new(<Frame as std::str::FromStr>::from_str(frame)?)
+        .with_polarization(polarization.map(|p| p.0).unwrap_or_default());
+    if let Some(circular) = circular {
+        sdme = sdme.with_circular_polarization(circular.0);
+    }
+    if production_amplitudes {
+        sdme = sdme.with_production_amplitudes();
+    }
+    Ok(sdme)
+}
+
+#[pyfunction]
+#[pyo3(name = "ThreePiSDME", signature = (name, frame="helicity", polarization=None, circular=None, production_amplitudes=false))]
+fn three_pi_sdme(
+    name: &str,
+    frame: &str,
+    polarization: Option<PyPolarization>,
+    circular: Option<PyCircularPolarization>,
+    production_amplitudes: bool,
+) -> PyResult<PyAmpOp> {
+    let sdme = py_three_pi_sdme_node(frame, polarization, circular, production_amplitudes)?;
+    Ok(Amplitude::new(name, Box::new(sdme)).into())
+}
+
+#[pyfunction]
+#[pyo3(name = "ThreePiSDMEGradient", signature = (frame="helicity", polarization=None, circular=None, production_amplitudes=false))]
+fn three_pi_sdme_gradient(
+    frame: &str,
+    polarization: Option<PyPolarization>,
+    circular: Option<PyCircularPolarization>,
+    production_amplitudes: bool,
+) -> PyResult<PyGradient> {
+    Ok(PyGradient::new(py_three_pi_sdme_node(
+        frame,
+        polarization,
+        circular,
+        production_amplitudes,
+    )?))
+}
+
+#[pyfunction]
+#[pyo3(name = "TwoBodySDME", signature = (name, j, frame="helicity", polarization=None))]
+fn two_body_sdme(
+    name: &str,
+    j: usize,
+    frame: &str,
+    polarization: Option<PyPolarization>,
+) -> PyResult<PyAmpOp> {
+    let sdme = TwoBodySDME::new(j, <Frame as std::str::FromStr>::from_str(frame)?)
+        .with_polarization(polarization.map(|p| p.0).unwrap_or_default());
+    Ok(Amplitude::new(name, Box::new(sdme)).into())
+}
+
+#[pyfunction]
+#[pyo3(name = "TwoBodySDMEGradient", signature = (j, frame="helicity", polarization=None))]
+fn two_body_sdme_gradient(
+    j: usize,
+    frame: &str,
+    polarization: Option<PyPolarization>,
+) -> PyResult<PyGradient> {
+    Ok(PyGradient::new(
+        TwoBodySDME::new(j, <Frame as std::str::FromStr>::from_str(frame)?)
+            .with_polarization(polarization.map(|p| p.0).unwrap_or_default()),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(name = "convert_sdmes", signature = (j, names, values, from_frame, to_frame, s, t, m_meson, covariance=None, m_beam=0.0, m_target=0.938272, m_recoil=0.938272))]
+#[allow(clippy::too_many_arguments)]
+fn py_convert_sdmes(
+    j: usize,
+    names: Vec<String>,
+    values: Vec<f64>,
+    from_frame: &str,
+    to_frame: &str,
+    s: f64,
+    t: f64,
+    m_meson: f64,
+    covariance: Option<Vec<Vec<f64>>>,
+    m_beam: f64,
+    m_target: f64,
+    m_recoil: f64,
+) -> PyResult<(Vec<f64>, Vec<Vec<f64>>)> {
+    let covariance = covariance_matrix(covariance, values.len())?;
+    let (values, covariance) = convert_sdmes(
+        j,
+        &names,
+        &DVector::from_vec(values),
+        &covariance,
+        &<Frame as std::str::FromStr>::from_str(from_frame)?,
+        &<Frame as std::str::FromStr>::from_str(to_frame)?,
+        &ProductionKinematics {
+            s,
+            t,
+            m_beam,
+            m_target,
+            m_meson,
+            m_recoil,
+        },
+    )?;
+    Ok((
+        values.iter().copied().collect(),
+        covariance
+            .row_iter()
+            .map(|row| row.iter().copied().collect())
+            .collect(),
+    ))
+}
+
+#[pyfunction]
+#[pyo3(name = "sdme_observables", signature = (names, values, covariance=None))]
+fn py_sdme_observables(
+    names: Vec<String>,
+    values: Vec<f64>,
+    covariance: Option<Vec<Vec<f64>>>,
+) -> PyResult<HashMap<String, (f64, f64)>> {
+    let observables = sdme_observables(
+        &names,
+        &values,
+        &covariance_matrix(covariance, values.len())?,
+    )?;
+    Ok(HashMap::from([
+        ("p_sigma".to_string(), observables.p_sigma),
+        ("natural".to_string(), observables.natural),
+        ("unnatural".to_string(), observables.unnatural),
+        ("beam_asymmetry".to_string(), observables.beam_asymmetry),
+    ]))
+}
+
+type PySDMEs = (Vec<String>, Vec<f64>, Vec<Vec<f64>>);
+
+#[pyfunction]
+#[pyo3(name = "sdmes_from_amplitudes", signature = (parameters, covariance=None))]
+fn py_sdmes_from_amplitudes(
+    parameters: Vec<f64>,
+    covariance: Option<Vec<Vec<f64>>>,
+) -> PyResult<PySDMEs> {
+    let (values, covariance) = sdme_covariance(
+        &parameters,
+        &covariance_matrix(covariance, N_AMPLITUDE_PARAMETERS)?,
+    )?;
+    Ok((
+        SDME_NAMES.iter().map(|name| name.to_string()).collect(),
+        values.iter().copied().collect(),
+        covariance
+            .row_iter()
+            .map(|row| row.iter().copied().collect())
+            .collect(),
+    ))
 }
 
 pub fn pyo3_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(two_pi_sdme, m)?)?;
+    m.add_function(wrap_pyfunction!(two_pi_sdme_gradient, m)?)?;
     m.add_function(wrap_pyfunction!(three_pi_sdme, m)?)?;
+    m.add_function(wrap_pyfunction!(three_pi_sdme_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(two_body_sdme, m)?)?;
+    m.add_function(wrap_pyfunction!(two_body_sdme_gradient, m)?)?;
+    m.add_function(wrap_pyfunction!(py_convert_sdmes, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sdme_observables, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sdmes_from_amplitudes, m)?)?;
     Ok(())
 }
@@ -1,10 +1,18 @@
-use std::{fmt::Display, str::FromStr};
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    str::FromStr,
+    sync::{OnceLock, RwLock},
+};
 
-use factorial::Factorial;
 use nalgebra::Vector3;
+use pyo3::{exceptions::PyRuntimeError, prelude::*, types::PyComplex};
+use rayon::prelude::*;
 use rustitude_core::prelude::*;
 use sphrs::Coordinates;
 
+use crate::errors::GluexError;
+
 pub fn breakup_momentum(m0: f64, m1: f64, m2: f64) -> f64 {
     f64::sqrt(f64::abs(
         m0.powi(4) + m1.powi(4) + m2.powi(4)
@@ -12,149 +20,646 @@ pub fn breakup_momentum(m0: f64, m1: f64, m2: f64) -> f64 {
     )) / (2.0 * m0)
 }
 
-pub fn blatt_weisskopf(m0: f64, m1: f64, m2: f64, l: usize) -> f64 {
+// d ln(q) / d m0
+pub(crate) fn breakup_momentum_log_derivative(m0: f64, m1: f64, m2: f64) -> f64 {
+    let lambda = m0.powi(4) + m1.powi(4) + m2.powi(4)
+        - 2.0 * (m0.powi(2) * m1.powi(2) + m0.powi(2) * m2.powi(2) + m1.powi(2) * m2.powi(2));
+    let dlambda = 4.0 * m0.powi(3) - 4.0 * m0 * (m1.powi(2) + m2.powi(2));
+    dlambda / (2.0 * lambda) - 1.0 / m0
+}
+
+pub const HBARC: f64 = 0.1973;
+
+// (exponent, coefficient) pairs of the polynomial in 1/z
+fn hankel_terms(l: usize) -> impl Iterator<Item = (i32, f64)> {
+    let factorial = |n: usize| (1..=n).map(|k| k as f64).product::<f64>();
+    (0..=l).map(move |k| {
+        (
+            -((l - k) as i32),
+            factorial(2 * l - k) * factorial(2 * l - 2 * k)
+                / (factorial(k) * factorial(l - k).powi(2) * 4.0f64.powi((l - k) as i32)),
+        )
+    })
+}
+
+fn hankel_modulus_sq(z: f64, l: usize) -> f64 {
+    hankel_terms(l).map(|(n, c)| c * z.powi(n)).sum()
+}
+
+fn hankel_modulus_sq_derivative(z: f64, l: usize) -> f64 {
+    hankel_terms(l)
+        .map(|(n, c)| c * n as f64 * z.powi(n - 1))
+        .sum()
+}
+
+pub fn blatt_weisskopf(m0: f64, m1: f64, m2: f64, l: usize, r: f64) -> f64 {
     let q = breakup_momentum(m0, m1, m2);
-    let z = q.powi(2) / f64::powi(0.1973, 2);
-    match l {
-        0 => 1.0,
-        1 => f64::sqrt((2.0 * z) / (z + 1.0)),
-        2 => f64::sqrt((13.0 * z.powi(2)) / ((z - 3.0).powi(2) + 9.0 * z)),
-        3 => f64::sqrt(
-            (277.0 * z.powi(3)) / (z * (z - 15.0).powi(2) + 9.0 * (2.0 * z - 5.0).powi(2)),
-        ),
-        4 => f64::sqrt(
-            (12746.0 * z.powi(4)) / (z.powi(2) - 45.0 * z + 105.0).powi(2)
-                + 25.0 * z * (2.0 * z - 21.0).powi(2),
-        ),
-        l => panic!("L = {l} is not yet implemented"),
-    }
-}
-
-pub fn small_wigner_d_matrix(beta: f64, j: usize, m: isize, n: isize) -> f64 {
-    let jpm = (j as i32 + m as i32) as u32;
-    let jmm = (j as i32 - m as i32) as u32;
-    let jpn = (j as i32 + n as i32) as u32;
-    let jmn = (j as i32 - n as i32) as u32;
-    let prefactor =
-        f64::sqrt((jpm.factorial() * jmm.factorial() * jpn.factorial() * jmn.factorial()) as f64);
-    let s_min = isize::max(0, n - m) as usize;
-    let s_max = isize::min(jpn as isize, jmm as isize) as usize;
-    let sum: f64 = (s_min..=s_max)
-        .map(|s| {
-            ((-1.0f64).powi(m as i32 - n as i32 + s as i32)
-                * (f64::cos(beta / 2.0)
-                    .powi(2 * (j as i32) + n as i32 - m as i32 - 2 * (s as i32)))
-                * (f64::sin(beta / 2.0).powi(m as i32 - n as i32 + 2 * s as i32)))
-                / ((jpm - s as u32).factorial()
-                    * (s as u32).factorial()
-                    * ((m - n + s as isize) as u32).factorial()
-                    * (jmm - s as u32).factorial()) as f64
-        })
-        .sum();
-    prefactor * sum
+    let z = (q * r / HBARC).powi(2);
+    f64::sqrt(hankel_modulus_sq(1.0, l) / hankel_modulus_sq(z, l))
+}
+
+// d ln(F_l) / d m0
+pub(crate) fn blatt_weisskopf_log_derivative(m0: f64, m1: f64, m2: f64, l: usize, r: f64) -> f64 {
+    let q = breakup_momentum(m0, m1, m2);
+    let z = (q * r / HBARC).powi(2);
+    -hankel_modulus_sq_derivative(z, l) / hankel_modulus_sq(z, l)
+        * z
+        * breakup_momentum_log_derivative(m0, m1, m2)
+}
+
+// Analytic derivatives of `Node::calculate` with respect to each of `Node::parameters`, in the
+// same order.
+pub trait Gradient: Node {
+    fn gradient(&self, parameters: &[f64], event: &Event) -> Result<Vec<Complex64>, NodeError>;
+}
+
+// Evaluates the gradient of a precalculated node for every event in the dataset, in event order.
+pub fn evaluate_gradient(
+    node: &dyn Gradient,
+    parameters: &[f64],
+    dataset: &Dataset,
+) -> Result<Vec<Vec<Complex64>>, NodeError> {
+    dataset
+        .events
+        .read()
+        .par_iter()
+        .map(|event| node.gradient(parameters, event))
+        .collect()
+}
+
+// Python handle on a node's analytic gradient. Call `precalculate` on a dataset before `evaluate`.
+#[pyclass(name = "Gradient")]
+pub struct PyGradient(pub Box<dyn Gradient>);
+
+impl PyGradient {
+    pub fn new<N: Gradient + 'static>(node: N) -> Self {
+        Self(Box::new(node))
+    }
+}
+
+#[pymethods]
+impl PyGradient {
+    fn parameters(&self) -> Vec<String> {
+        self.0.parameters()
+    }
+    fn precalculate(&mut self, dataset: Dataset) -> PyResult<()> {
+        self.0
+            .precalculate(&dataset)
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))
+    }
+    fn evaluate(
+        &self,
+        py: Python<'_>,
+        parameters: Vec<f64>,
+        dataset: Dataset,
+    ) -> PyResult<Vec<Vec<Py<PyComplex>>>> {
+        let gradients = evaluate_gradient(self.0.as_ref(), &parameters, &dataset)
+            .map_err(|err| PyRuntimeError::new_err(err.to_string()))?;
+        Ok(gradients
+            .iter()
+            .map(|gradient| {
+                gradient
+                    .iter()
+                    .map(|d| PyComplex::from_doubles_bound(py, d.re, d.im).unbind())
+                    .collect()
+            })
+            .collect())
+    }
+}
+
+// Gradient of sqrt(|w|) given the gradient of w.
+pub(crate) fn sqrt_abs_gradient(w: f64, dw: &[f64]) -> Vec<Complex64> {
+    if w == 0.0 {
+        return vec![Complex64::default(); dw.len()];
+    }
+    let scale = w.signum() / (2.0 * w.abs().sqrt());
+    dw.iter().map(|d| Complex64::from(d * scale)).collect()
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (1..=k).map(|i| (n - k + i) as f64 / i as f64).product()
+}
+
+fn jacobi_polynomial(n: usize, a: usize, b: usize, x: f64) -> f64 {
+    let (a, b) = (a as f64, b as f64);
+    let mut p_prev = 1.0;
+    if n == 0 {
+        return p_prev;
+    }
+    let mut p = (a + 1.0) + (a + b + 2.0) * (x - 1.0) / 2.0;
+    for k in 2..=n {
+        let k = k as f64;
+        let c = 2.0 * k + a + b;
+        let p_next = ((c - 1.0) * (c * (c - 2.0) * x + a * a - b * b) * p
+            - 2.0 * (k + a - 1.0) * (k + b - 1.0) * c * p_prev)
+            / (2.0 * k * (k + a + b) * (c - 2.0));
+        p_prev = p;
+        p = p_next;
+    }
+    p
+}
+
+pub fn small_wigner_d_matrix(
+    beta: f64,
+    two_j: usize,
+    two_m: isize,
+    two_n: isize,
+) -> Result<f64, GluexError> {
+    let two_j_signed = two_j as isize;
+    if two_m.abs() > two_j_signed
+        || two_n.abs() > two_j_signed
+        || (two_j_signed + two_m) % 2 != 0
+        || (two_j_signed + two_n) % 2 != 0
+    {
+        return Err(GluexError::InvalidSpinProjection {
+            two_j,
+            two_m,
+            two_n,
+        });
+    }
+    let jpm = ((two_j_signed + two_m) / 2) as usize;
+    let jmm = ((two_j_signed - two_m) / 2) as usize;
+    let jpn = ((two_j_signed + two_n) / 2) as usize;
+    let jmn = ((two_j_signed - two_n) / 2) as usize;
+    let k = jpm.min(jmm).min(jpn).min(jmn);
+    let (a, lambda) = if k == jpn {
+        ((two_m - two_n).unsigned_abs() / 2, (two_m - two_n) / 2)
+    } else if k == jmn || k == jpm {
+        ((two_n - two_m).unsigned_abs() / 2, 0)
+    } else {
+        ((two_m - two_n).unsigned_abs() / 2, (two_m - two_n) / 2)
+    };
+    let b = two_j - 2 * k - a;
+    let sign = if lambda % 2 == 0 { 1.0 } else { -1.0 };
+    Ok(sign
+        * f64::sqrt(binomial(two_j - k, k + a) / binomial(k + b, b))
+        * f64::sin(beta / 2.0).powi(a as i32)
+        * f64::cos(beta / 2.0).powi(b as i32)
+        * jacobi_polynomial(k, a, b, beta.cos()))
 }
 
 pub fn wigner_d_matrix(
     alpha: f64,
     beta: f64,
     gamma: f64,
-    j: usize,
-    m: isize,
-    n: isize,
-) -> Complex64 {
-    Complex64::cis(-(m as f64) * alpha)
-        * small_wigner_d_matrix(beta, j, m, n)
-        * Complex64::cis(-(n as f64) * gamma)
+    two_j: usize,
+    two_m: isize,
+    two_n: isize,
+) -> Result<Complex64, GluexError> {
+    Ok(Complex64::cis(-(two_m as f64) / 2.0 * alpha)
+        * small_wigner_d_matrix(beta, two_j, two_m, two_n)?
+        * Complex64::cis(-(two_n as f64) / 2.0 * gamma))
 }
 
-#[derive(Clone, Copy, Default)]
-#[rustfmt::skip]
-pub enum Wave {
-    #[default]
-    S,
-    S0,
-    Pn1, P0, P1, P,
-    Dn2, Dn1, D0, D1, D2, D,
-    Fn3, Fn2, Fn1, F0, F1, F2, F3, F,
+// Angular-momentum coefficients are evaluated from the Racah formulae with every factorial kept
+// as a vector of prime exponents, so the only rounding happens in the final conversion to f64.
+fn primes_up_to(n: usize) -> Vec<usize> {
+    let mut sieve = vec![true; n + 1];
+    (2..=n)
+        .filter(|&p| {
+            if sieve[p] {
+                (p * p..=n).step_by(p).for_each(|k| sieve[k] = false);
+            }
+            sieve[p]
+        })
+        .collect()
 }
 
-#[rustfmt::skip]
-impl Wave {
-    pub fn new(l: usize, m: isize) -> Self {
-        match l {
-            0 => Self::S0,
-            1 => match m {
-                -1 => Self::Pn1,
-                0 => Self::P0,
-                1 => Self::P1,
-                _ => panic!("|m = {m}| > (l = {l})")
+fn factorial_exponents(n: usize, primes: &[usize]) -> Vec<i64> {
+    primes
+        .iter()
+        .map(|&p| {
+            let mut exponent = 0;
+            let mut power = p;
+            while power <= n {
+                exponent += (n / power) as i64;
+                power *= p;
             }
-            2 => match m {
-                -2 => Self::Dn2,
-                -1 => Self::Dn1,
-                0 => Self::D0,
-                1 => Self::D1,
-                2 => Self::D2,
-                _ => panic!("|m = {m}| > (l = {l})")
-            }
-            3 => match m {
-                -3 => Self::Fn3,
-                -2 => Self::Fn2,
-                -1 => Self::Fn1,
-                0 => Self::F0,
-                1 => Self::F1,
-                2 => Self::F2,
-                3 => Self::F3,
-                _ => panic!("|m = {m}| > (l = {l})")
-            }
-            _ => panic!("(l = {l}) > 3 is not yet implemented!")
+            exponent
+        })
+        .collect()
+}
+
+fn factorial_ratio(numerator: &[usize], denominator: &[usize], primes: &[usize]) -> Vec<i64> {
+    let mut exponents = vec![0; primes.len()];
+    for &n in numerator {
+        for (e, f) in exponents.iter_mut().zip(factorial_exponents(n, primes)) {
+            *e += f;
         }
     }
-    pub fn l(&self) -> i64 {
-        match self {
-            Self::S0 | Self::S => 0,
-            Self::Pn1 | Self::P0 | Self::P1 | Self::P => 1,
-            Self::Dn2 | Self::Dn1 | Self::D0 | Self::D1 | Self::D2 | Self::D => 2,
-            Self::Fn3 | Self::Fn2 | Self::Fn1 | Self::F0 | Self::F1 | Self::F2 | Self::F3 | Self::F => 3,
+    for &n in denominator {
+        for (e, f) in exponents.iter_mut().zip(factorial_exponents(n, primes)) {
+            *e -= f;
         }
     }
-    pub fn m(&self) -> i64 {
-        match self {
-            Self::S | Self::P | Self::D | Self::F => 0,
-            Self::S0 | Self::P0 | Self::D0 | Self::F0 => 0,
-            Self::Pn1 | Self::Dn1 | Self::Fn1 => -1,
-            Self::P1 | Self::D1 | Self::F1 => 1,
-            Self::Dn2 | Self::Fn2 => -2,
-            Self::D2 | Self::F2 => 2,
-            Self::Fn3 => -3,
-            Self::F3 => 3,
+    exponents
+}
+
+// Evaluates sqrt(root) * sum_k sign_k * term_k, where root and every term are products of
+// factorials given as prime exponents.
+fn racah_value(root: &[i64], terms: &[(bool, Vec<i64>)], primes: &[usize]) -> f64 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+    let common: Vec<i64> = (0..primes.len())
+        .map(|i| terms.iter().map(|(_, t)| t[i]).min().unwrap_or(0))
+        .collect();
+    let integer_terms: Option<Vec<i128>> = terms
+        .iter()
+        .map(|(negative, t)| {
+            let magnitude = primes
+                .iter()
+                .zip(t.iter().zip(&common))
+                .try_fold(1i128, |acc, (&p, (&e, &c))| {
+                    acc.checked_mul((p as i128).checked_pow((e - c) as u32)?)
+                })?;
+            Some(if *negative { -magnitude } else { magnitude })
+        })
+        .collect();
+    let sum = integer_terms
+        .and_then(|ts| ts.into_iter().try_fold(0i128, |acc, t| acc.checked_add(t)))
+        .map(|sum| sum as f64)
+        .unwrap_or_else(|| {
+            terms
+                .iter()
+                .map(|(negative, t)| {
+                    let magnitude: f64 = primes
+                        .iter()
+                        .zip(t.iter().zip(&common))
+                        .map(|(&p, (&e, &c))| (p as f64).powi((e - c) as i32))
+                        .product();
+                    if *negative {
+                        -magnitude
+                    } else {
+                        magnitude
+                    }
+                })
+                .sum()
+        });
+    if sum == 0.0 {
+        return 0.0;
+    }
+    sum * primes
+        .iter()
+        .zip(root.iter().zip(&common))
+        .map(|(&p, (&r, &c))| (p as f64).powf((r + 2 * c) as f64 / 2.0))
+        .product::<f64>()
+}
+
+pub(crate) fn triangle(two_a: usize, two_b: usize, two_c: usize) -> bool {
+    two_c <= two_a + two_b
+        && two_a <= two_b + two_c
+        && two_b <= two_c + two_a
+        && (two_a + two_b + two_c) & 1 == 0
+}
+
+pub(crate) fn check_projection(two_j: usize, two_m: isize) -> Result<(), GluexError> {
+    if two_m.unsigned_abs() > two_j || (two_j as isize + two_m) % 2 != 0 {
+        return Err(GluexError::InvalidProjection { two_j, two_m });
+    }
+    Ok(())
+}
+
+type CoefficientCache = RwLock<HashMap<(char, [isize; 6]), f64>>;
+
+fn cached<F: FnOnce() -> f64>(key: (char, [isize; 6]), f: F) -> f64 {
+    static CACHE: OnceLock<CoefficientCache> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| RwLock::new(HashMap::new()));
+    if let Some(value) = cache
+        .read()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&key)
+    {
+        return *value;
+    }
+    let value = f();
+    cache
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(key, value);
+    value
+}
+
+pub fn wigner_3j(
+    two_j1: usize,
+    two_j2: usize,
+    two_j3: usize,
+    two_m1: isize,
+    two_m2: isize,
+    two_m3: isize,
+) -> Result<f64, GluexError> {
+    check_projection(two_j1, two_m1)?;
+    check_projection(two_j2, two_m2)?;
+    check_projection(two_j3, two_m3)?;
+    if two_m1 + two_m2 + two_m3 != 0 || !triangle(two_j1, two_j2, two_j3) {
+        return Ok(0.0);
+    }
+    let key = (
+        '3',
+        [
+            two_j1 as isize,
+            two_j2 as isize,
+            two_j3 as isize,
+            two_m1,
+            two_m2,
+            two_m3,
+        ],
+    );
+    Ok(cached(key, || {
+        let (j1, j2, j3) = (two_j1 as isize, two_j2 as isize, two_j3 as isize);
+        let half = |x: isize| (x / 2) as usize;
+        let primes = primes_up_to(half(j1 + j2 + j3) + 1);
+        let root = factorial_ratio(
+            &[
+                half(j1 + j2 - j3),
+                half(j1 - j2 + j3),
+                half(-j1 + j2 + j3),
+                half(j1 + two_m1),
+                half(j1 - two_m1),
+                half(j2 + two_m2),
+                half(j2 - two_m2),
+                half(j3 + two_m3),
+                half(j3 - two_m3),
+            ],
+            &[half(j1 + j2 + j3) + 1],
+            &primes,
+        );
+        let k_min = [0, (j2 - j3 - two_m1) / 2, (j1 - j3 + two_m2) / 2]
+            .into_iter()
+            .max()
+            .unwrap_or(0);
+        let k_max = [(j1 + j2 - j3) / 2, (j1 - two_m1) / 2, (j2 + two_m2) / 2]
+            .into_iter()
+            .min()
+            .unwrap_or(0);
+        let terms: Vec<(bool, Vec<i64>)> = (k_min..=k_max)
+            .map(|k| {
+                (
+                    k % 2 != 0,
+                    factorial_ratio(
+                        &[],
+                        &[
+                            k as usize,
+                            (k + (j3 - j2 + two_m1) / 2) as usize,
+                            (k + (j3 - j1 - two_m2) / 2) as usize,
+                            ((j1 + j2 - j3) / 2 - k) as usize,
+                            ((j1 - two_m1) / 2 - k) as usize,
+                            ((j2 + two_m2) / 2 - k) as usize,
+                        ],
+                        &primes,
+                    ),
+                )
+            })
+            .collect();
+        let phase = if ((j1 - j2 - two_m3) / 2) % 2 == 0 {
+            1.0
+        } else {
+            -1.0
+        };
+        phase * racah_value(&root, &terms, &primes)
+    }))
+}
+
+pub fn clebsch_gordan(
+    two_j1: usize,
+    two_m1: isize,
+    two_j2: usize,
+    two_m2: isize,
+    two_j: usize,
+    two_m: isize,
+) -> Result<f64, GluexError> {
+    let three_j = wigner_3j(two_j1, two_j2, two_j, two_m1, two_m2, -two_m)?;
+    let phase = if ((two_j1 as isize - two_j2 as isize + two_m) / 2) % 2 == 0 {
+        1.0
+    } else {
+        -1.0
+    };
+    Ok(phase * f64::sqrt(two_j as f64 + 1.0) * three_j)
+}
+
+pub fn wigner_6j(
+    two_j1: usize,
+    two_j2: usize,
+    two_j3: usize,
+    two_j4: usize,
+    two_j5: usize,
+    two_j6: usize,
+) -> f64 {
+    if !(triangle(two_j1, two_j2, two_j3)
+        && triangle(two_j1, two_j5, two_j6)
+        && triangle(two_j4, two_j2, two_j6)
+        && triangle(two_j4, two_j5, two_j3))
+    {
+        return 0.0;
+    }
+    let key = (
+        '6',
+        [
+            two_j1 as isize,
+            two_j2 as isize,
+            two_j3 as isize,
+            two_j4 as isize,
+            two_j5 as isize,
+            two_j6 as isize,
+        ],
+    );
+    cached(key, || {
+        let triads = [
+            (two_j1, two_j2, two_j3),
+            (two_j1, two_j5, two_j6),
+            (two_j4, two_j2, two_j6),
+            (two_j4, two_j5, two_j3),
+        ];
+        let a: Vec<usize> = triads.iter().map(|(x, y, z)| (x + y + z) / 2).collect();
+        let b = [
+            (two_j1 + two_j2 + two_j4 + two_j5) / 2,
+            (two_j2 + two_j3 + two_j5 + two_j6) / 2,
+            (two_j3 + two_j1 + two_j6 + two_j4) / 2,
+        ];
+        let t_min = a.iter().copied().max().unwrap_or(0);
+        let t_max = b.iter().copied().min().unwrap_or(0);
+        let primes = primes_up_to(t_max + 1);
+        let mut root_numerator = Vec::new();
+        let mut root_denominator = Vec::new();
+        for (x, y, z) in triads {
+            root_numerator.extend([(x + y - z) / 2, (x + z - y) / 2, (y + z - x) / 2]);
+            root_denominator.push((x + y + z) / 2 + 1);
+        }
+        let root = factorial_ratio(&root_numerator, &root_denominator, &primes);
+        let terms: Vec<(bool, Vec<i64>)> = (t_min..=t_max)
+            .map(|t| {
+                (
+                    t % 2 != 0,
+                    factorial_ratio(
+                        &[t + 1],
+                        &[
+                            t - a[0],
+                            t - a[1],
+                            t - a[2],
+                            t - a[3],
+                            b[0] - t,
+                            b[1] - t,
+                            b[2] - t,
+                        ],
+                        &primes,
+                    ),
+                )
+            })
+            .collect();
+        racah_value(&root, &terms, &primes)
+    })
+}
+
+pub(crate) fn extract_complex(value: &Bound<'_, PyAny>) -> PyResult<Complex64> {
+    if let Ok(value) = value.downcast::<PyComplex>() {
+        Ok(Complex64::new(value.real(), value.imag()))
+    } else {
+        Ok(Complex64::new(value.extract()?, 0.0))
+    }
+}
+
+// Accepts either a wave label like "D+2" or an integer l together with m (which defaults to 0).
+pub(crate) fn extract_wave(l: &Bound<'_, PyAny>, m: Option<isize>) -> PyResult<Wave> {
+    if let Ok(label) = l.extract::<String>() {
+        Ok(Wave::from_str(&label)?)
+    } else {
+        Ok(Wave::new(l.extract()?, m.unwrap_or_default())?)
+    }
+}
+
+pub(crate) fn doubled(x: f64) -> Result<isize, GluexError> {
+    let two_x = 2.0 * x;
+    if (two_x - two_x.round()).abs() > 1e-9 {
+        return Err(GluexError::InvalidHalfInteger(x));
+    }
+    Ok(two_x.round() as isize)
+}
+
+pub(crate) fn doubled_spin(x: f64) -> Result<usize, GluexError> {
+    let two_x = doubled(x)?;
+    if two_x < 0 {
+        return Err(GluexError::InvalidHalfInteger(x));
+    }
+    Ok(two_x as usize)
+}
+
+#[pyfunction(name = "clebsch_gordan")]
+fn py_clebsch_gordan(j1: f64, m1: f64, j2: f64, m2: f64, j: f64, m: f64) -> PyResult<f64> {
+    Ok(clebsch_gordan(
+        doubled_spin(j1)?,
+        doubled(m1)?,
+        doubled_spin(j2)?,
+        doubled(m2)?,
+        doubled_spin(j)?,
+        doubled(m)?,
+    )?)
+}
+
+#[pyfunction(name = "wigner_3j")]
+fn py_wigner_3j(j1: f64, j2: f64, j3: f64, m1: f64, m2: f64, m3: f64) -> PyResult<f64> {
+    Ok(wigner_3j(
+        doubled_spin(j1)?,
+        doubled_spin(j2)?,
+        doubled_spin(j3)?,
+        doubled(m1)?,
+        doubled(m2)?,
+        doubled(m3)?,
+    )?)
+}
+
+#[pyfunction(name = "wigner_6j")]
+fn py_wigner_6j(j1: f64, j2: f64, j3: f64, j4: f64, j5: f64, j6: f64) -> PyResult<f64> {
+    Ok(wigner_6j(
+        doubled_spin(j1)?,
+        doubled_spin(j2)?,
+        doubled_spin(j3)?,
+        doubled_spin(j4)?,
+        doubled_spin(j5)?,
+        doubled_spin(j6)?,
+    ))
+}
+
+pub fn pyo3_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyGradient>()?;
+    m.add_function(wrap_pyfunction!(py_clebsch_gordan, m)?)?;
+    m.add_function(wrap_pyfunction!(py_wigner_3j, m)?)?;
+    m.add_function(wrap_pyfunction!(py_wigner_6j, m)?)?;
+    Ok(())
+}
+
+const SPECTROSCOPIC_LETTERS: [char; 21] = [
+    'S', 'P', 'D', 'F', 'G', 'H', 'I', 'K', 'L', 'M', 'N', 'O', 'Q', 'R', 'T', 'U', 'V', 'W', 'X',
+    'Y', 'Z',
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Wave {
+    l: usize,
+    m: isize,
+}
+
+impl Wave {
+    pub fn new(l: usize, m: isize) -> Result<Self, GluexError> {
+        if m.unsigned_abs() > l {
+            return Err(GluexError::InvalidWave { l, m });
         }
+        Ok(Self { l, m })
+    }
+    pub fn l(&self) -> i64 {
+        self.l as i64
+    }
+    pub fn m(&self) -> i64 {
+        self.m as i64
+    }
+}
+
+impl FromStr for Wave {
+    type Err = GluexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || GluexError::ParseWave(s.to_string());
+        let label = s.trim().to_uppercase().replace(' ', "");
+        let (l, m_string) = match label.chars().next() {
+            Some(c) if c.is_ascii_alphabetic() => (
+                SPECTROSCOPIC_LETTERS
+                    .iter()
+                    .position(|&letter| letter == c)
+                    .ok_or_else(err)?,
+                &label[1..],
+            ),
+            _ => {
+                let split = label.find(['+', '-']).ok_or_else(err)?;
+                (label[..split].parse().map_err(|_| err())?, &label[split..])
+            }
+        };
+        let m = match m_string {
+            "" => 0,
+            m_string => m_string.parse().map_err(|_| err())?,
+        };
+        Self::new(l, m)
     }
 }
 
 impl Display for Wave {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let l_string = match self.l() {
-            0 => "S",
-            1 => "P",
-            2 => "D",
-            3 => "F",
-            _ => unimplemented!(),
-        };
-        write!(f, "{} {:+}", l_string, self.m())
+        match SPECTROSCOPIC_LETTERS.get(self.l) {
+            Some(letter) => write!(f, "{} {:+}", letter, self.m),
+            None => write!(f, "{} {:+}", self.l, self.m),
+        }
     }
 }
 
+#[derive(Clone, Copy, Debug)]
 pub enum Frame {
     Helicity,
     GottfriedJackson,
+    CMHelicity,
+    Adair,
+    Canonical,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct ParseFrameError;
-
 impl FromStr for Frame {
-    type Err = ParseFrameError;
+    type Err = GluexError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_ref() {
@@ -162,11 +667,35 @@ impl FromStr for Frame {
             "hx" => Ok(Frame::Helicity),
             "gottfried-jackson" => Ok(Frame::GottfriedJackson),
             "gj" => Ok(Frame::GottfriedJackson),
-            _ => Err(ParseFrameError),
+            "cm-helicity" => Ok(Frame::CMHelicity),
+            "helicity-cm" => Ok(Frame::CMHelicity),
+            "hx-cm" => Ok(Frame::CMHelicity),
+            "adair" => Ok(Frame::Adair),
+            "canonical" => Ok(Frame::Canonical),
+            _ => Err(GluexError::ParseFrame(s.to_string())),
         }
     }
 }
 
+fn reversed(p4: &FourMomentum) -> FourMomentum {
+    FourMomentum::new(p4.e(), -p4.px(), -p4.py(), -p4.pz())
+}
+
+// Carries a direction from the resonance rest frame reached by a pure boost from the γp CM into
+// the one reached directly from the lab, picking up the Wigner rotation between the two.
+fn from_cm_rest_frame(
+    v: &Vector3<f64>,
+    resonance: &FourMomentum,
+    cm: &FourMomentum,
+) -> Vector3<f64> {
+    FourMomentum::new(1.0, v.x, v.y, v.z)
+        .boost_along(&reversed(&resonance.boost_along(cm)))
+        .boost_along(&reversed(cm))
+        .boost_along(resonance)
+        .momentum()
+        .normalize()
+}
+
 impl Frame {
     pub fn coordinates(
         &self,
@@ -174,7 +703,7 @@ impl Frame {
         daughter_res_vec: &Vector3<f64>,
         event: &Event,
     ) -> (Vector3<f64>, Vector3<f64>, Vector3<f64>, Coordinates<f64>) {
-        match self {
+        let (x, y, z) = match self {
             Frame::Helicity => {
                 let z = resonance.momentum().normalize();
                 let y = event
@@ -182,17 +711,7 @@ impl Frame {
                     .momentum()
                     .cross(&(resonance.momentum()))
                     .normalize();
-                let x = y.cross(&z);
-                (
-                    x,
-                    y,
-                    z,
-                    Coordinates::cartesian(
-                        daughter_res_vec.dot(&x),
-                        daughter_res_vec.dot(&y),
-                        daughter_res_vec.dot(&z),
-                    ),
-                )
+                (y.cross(&z), y, z)
             }
             Frame::GottfriedJackson => {
                 let z = event.beam_p4.boost_along(resonance).momentum().normalize();
@@ -201,33 +720,57 @@ impl Frame {
                     .momentum()
                     .cross(&(resonance.momentum()))
                     .normalize();
+                (y.cross(&z), y, z)
+            }
+            Frame::CMHelicity => {
+                let z = -event
+                    .recoil_p4
+                    .boost_along(resonance)
+                    .momentum()
+                    .normalize();
+                let y = event
+                    .beam_p4
+                    .momentum()
+                    .cross(&(-event.recoil_p4.momentum()))
+                    .normalize();
+                (y.cross(&z), y, z)
+            }
+            Frame::Adair | Frame::Canonical => {
+                let cm = *resonance + event.recoil_p4;
+                let z = event.beam_p4.boost_along(&cm).momentum().normalize();
+                let y = match self {
+                    Frame::Adair => z.cross(&resonance.boost_along(&cm).momentum()).normalize(),
+                    _ => z.cross(&Vector3::x()).normalize(),
+                };
                 let x = y.cross(&z);
                 (
-                    x,
-                    y,
-                    z,
-                    Coordinates::cartesian(
-                        daughter_res_vec.dot(&x),
-                        daughter_res_vec.dot(&y),
-                        daughter_res_vec.dot(&z),
-                    ),
+                    from_cm_rest_frame(&x, resonance, &cm),
+                    from_cm_rest_frame(&y, resonance, &cm),
+                    from_cm_rest_frame(&z, resonance, &cm),
                 )
             }
-        }
+        };
+        (
+            x,
+            y,
+            z,
+            Coordinates::cartesian(
+                daughter_res_vec.dot(&x),
+                daughter_res_vec.dot(&y),
+                daughter_res_vec.dot(&z),
+            ),
+        )
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Reflectivity {
     Positive = 1,
     Negative = -1,
 }
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct ParseReflectivityError;
-
 impl FromStr for Reflectivity {
-    type Err = ParseReflectivityError;
+    type Err = GluexError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_ref() {
@@ -242,7 +785,7 @@ impl FromStr for Reflectivity {
             "-" => Ok(Reflectivity::Negative),
             "minus" => Ok(Reflectivity::Negative),
             "m" => Ok(Reflectivity::Negative),
-            _ => Err(ParseReflectivityError),
+            _ => Err(GluexError::ParseReflectivity(s.to_string())),
         }
     }
 }
@@ -255,3 +798,210 @@ impl Display for Reflectivity {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn assert_close(found: f64, expected: f64) {
+        assert!(
+            (found - expected).abs() < 1e-12,
+            "found {found}, expected {expected}"
+        );
+    }
+
+    #[test]
+    fn small_wigner_d_matches_closed_forms() {
+        for beta in [0.0, 0.4, 1.3, 2.2, PI] {
+            let (c, s) = (beta.cos(), beta.sin());
+            let (ch, sh) = (f64::cos(beta / 2.0), f64::sin(beta / 2.0));
+            let d = |two_j, two_m, two_n| small_wigner_d_matrix(beta, two_j, two_m, two_n).unwrap();
+            assert_close(d(1, 1, 1), ch);
+            assert_close(d(1, 1, -1), -sh);
+            assert_close(d(1, -1, 1), sh);
+            assert_close(d(1, -1, -1), ch);
+            assert_close(d(2, 2, 2), (1.0 + c) / 2.0);
+            assert_close(d(2, 2, 0), -s / f64::sqrt(2.0));
+            assert_close(d(2, 0, 2), s / f64::sqrt(2.0));
+            assert_close(d(2, 2, -2), (1.0 - c) / 2.0);
+            assert_close(d(2, 0, 0), c);
+            assert_close(d(2, -2, 0), s / f64::sqrt(2.0));
+            assert_close(d(3, 3, 3), (1.0 + c) / 2.0 * ch);
+            assert_close(d(3, 3, 1), -f64::sqrt(3.0) * (1.0 + c) / 2.0 * sh);
+            assert_close(d(3, 3, -1), f64::sqrt(3.0) * (1.0 - c) / 2.0 * ch);
+            assert_close(d(3, 3, -3), -(1.0 - c) / 2.0 * sh);
+            assert_close(d(3, 1, 1), (3.0 * c - 1.0) / 2.0 * ch);
+            assert_close(d(3, 1, -1), -(3.0 * c + 1.0) / 2.0 * sh);
+            assert_close(d(3, -1, 3), f64::sqrt(3.0) * (1.0 - c) / 2.0 * ch);
+        }
+    }
+
+    #[test]
+    fn small_wigner_d_is_orthogonal() {
+        let beta = 1.1;
+        for two_j in 0..=8 {
+            let projections = || (-(two_j as isize)..=two_j as isize).step_by(2);
+            for two_m in projections() {
+                for two_n in projections() {
+                    let product = projections()
+                        .map(|two_k| {
+                            small_wigner_d_matrix(beta, two_j, two_m, two_k).unwrap()
+                                * small_wigner_d_matrix(beta, two_j, two_n, two_k).unwrap()
+                        })
+                        .sum::<f64>();
+                    assert_close(product, if two_m == two_n { 1.0 } else { 0.0 });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn small_wigner_d_rejects_invalid_projections() {
+        assert!(small_wigner_d_matrix(0.3, 2, 4, 0).is_err());
+        assert!(small_wigner_d_matrix(0.3, 2, 1, 0).is_err());
+        assert!(small_wigner_d_matrix(0.3, 1, 1, 0).is_err());
+    }
+
+    #[test]
+    fn clebsch_gordan_matches_tables() {
+        let cg = |j1, m1, j2, m2, j, m| clebsch_gordan(j1, m1, j2, m2, j, m).unwrap();
+        // ½ ⊗ ½
+        assert_close(cg(1, 1, 1, 1, 2, 2), 1.0);
+        assert_close(cg(1, 1, 1, -1, 2, 0), f64::sqrt(0.5));
+        assert_close(cg(1, 1, 1, -1, 0, 0), f64::sqrt(0.5));
+        assert_close(cg(1, -1, 1, 1, 0, 0), -f64::sqrt(0.5));
+        // 1 ⊗ ½
+        assert_close(cg(2, 2, 1, -1, 3, 1), f64::sqrt(1.0 / 3.0));
+        assert_close(cg(2, 0, 1, 1, 3, 1), f64::sqrt(2.0 / 3.0));
+        assert_close(cg(2, 2, 1, -1, 1, 1), f64::sqrt(2.0 / 3.0));
+        assert_close(cg(2, 0, 1, 1, 1, 1), -f64::sqrt(1.0 / 3.0));
+        assert_close(cg(2, -2, 1, 1, 1, -1), -f64::sqrt(2.0 / 3.0));
+        // 1 ⊗ 1
+        assert_close(cg(2, 2, 2, -2, 4, 0), f64::sqrt(1.0 / 6.0));
+        assert_close(cg(2, 0, 2, 0, 4, 0), f64::sqrt(2.0 / 3.0));
+        assert_close(cg(2, 2, 2, -2, 2, 0), f64::sqrt(0.5));
+        assert_close(cg(2, -2, 2, 2, 2, 0), -f64::sqrt(0.5));
+        assert_close(cg(2, 0, 2, 0, 2, 0), 0.0);
+        assert_close(cg(2, 2, 2, -2, 0, 0), f64::sqrt(1.0 / 3.0));
+        assert_close(cg(2, 0, 2, 0, 0, 0), -f64::sqrt(1.0 / 3.0));
+        // projections that don't add up
+        assert_close(cg(2, 2, 2, 0, 2, 0), 0.0);
+        assert!(clebsch_gordan(2, 1, 2, 0, 2, 1).is_err());
+    }
+
+    #[test]
+    fn clebsch_gordan_exchange_symmetry() {
+        // ⟨j₁m₁j₂m₂|JM⟩ = (-1)^{j₁+j₂-J} ⟨j₂m₂j₁m₁|JM⟩
+        for (two_j1, two_j2) in [(1, 2), (3, 2), (3, 3), (4, 3)] {
+            for two_j in (two_j1.max(two_j2) - two_j1.min(two_j2)..=two_j1 + two_j2).step_by(2) {
+                let sign = if ((two_j1 + two_j2 - two_j) / 2) % 2 == 0 {
+                    1.0
+                } else {
+                    -1.0
+                };
+                for two_m1 in (-(two_j1 as isize)..=two_j1 as isize).step_by(2) {
+                    for two_m2 in (-(two_j2 as isize)..=two_j2 as isize).step_by(2) {
+                        let two_m = two_m1 + two_m2;
+                        if two_m.unsigned_abs() > two_j {
+                            continue;
+                        }
+                        assert_close(
+                            clebsch_gordan(two_j1, two_m1, two_j2, two_m2, two_j, two_m).unwrap(),
+                            sign * clebsch_gordan(two_j2, two_m2, two_j1, two_m1, two_j, two_m)
+                                .unwrap(),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wigner_3j_matches_tables() {
+        let three_j = |j1, j2, j3, m1, m2, m3| wigner_3j(j1, j2, j3, m1, m2, m3).unwrap();
+        // (j j 0; m -m 0) = (-1)^{j-m} / √(2j+1)
+        assert_close(three_j(1, 1, 0, 1, -1, 0), f64::sqrt(0.5));
+        assert_close(three_j(1, 1, 0, -1, 1, 0), -f64::sqrt(0.5));
+        assert_close(three_j(2, 2, 0, 2, -2, 0), f64::sqrt(1.0 / 3.0));
+        assert_close(three_j(2, 2, 0, 0, 0, 0), -f64::sqrt(1.0 / 3.0));
+        assert_close(three_j(3, 3, 0, 1, -1, 0), -0.5);
+        assert_close(three_j(1, 1, 2, 1, -1, 0), f64::sqrt(1.0 / 6.0));
+        assert_close(three_j(2, 2, 4, 0, 0, 0), f64::sqrt(2.0 / 15.0));
+        assert_close(three_j(4, 4, 4, 0, 0, 0), -f64::sqrt(2.0 / 35.0));
+        // odd columns vanish when every projection is zero
+        assert_close(three_j(2, 2, 2, 0, 0, 0), 0.0);
+        assert_close(three_j(2, 4, 6, 2, 0, 0), 0.0);
+    }
+
+    #[test]
+    fn wigner_3j_is_orthogonal() {
+        // Σ_{m₁m₂} (j₁ j₂ j; m₁ m₂ m)(j₁ j₂ j'; m₁ m₂ m) = δ_{jj'} / (2j+1)
+        let (two_j1, two_j2, two_m): (usize, usize, isize) = (3, 4, 1);
+        for two_j in (1..=7).step_by(2) {
+            for two_j_prime in (1..=7).step_by(2) {
+                let sum = (-3..=3)
+                    .step_by(2)
+                    .map(|two_m1| {
+                        let two_m2 = -two_m - two_m1;
+                        if two_m2.unsigned_abs() > two_j2 {
+                            return 0.0;
+                        }
+                        wigner_3j(two_j1, two_j2, two_j, two_m1, two_m2, two_m).unwrap()
+                            * wigner_3j(two_j1, two_j2, two_j_prime, two_m1, two_m2, two_m).unwrap()
+                    })
+                    .sum::<f64>();
+                let expected = if two_j == two_j_prime {
+                    1.0 / (two_j as f64 + 1.0)
+                } else {
+                    0.0
+                };
+                assert_close(sum, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn wigner_6j_matches_tables() {
+        // {a b c; b a 0} = (-1)^{a+b+c} / √((2a+1)(2b+1))
+        assert_close(wigner_6j(1, 1, 0, 1, 1, 0), -0.5);
+        assert_close(wigner_6j(1, 1, 2, 1, 1, 0), 0.5);
+        assert_close(wigner_6j(2, 2, 2, 2, 2, 0), -1.0 / 3.0);
+        assert_close(wigner_6j(2, 2, 4, 2, 2, 0), 1.0 / 3.0);
+        assert_close(wigner_6j(3, 2, 1, 2, 3, 0), -f64::sqrt(1.0 / 12.0));
+        assert_close(wigner_6j(1, 1, 2, 1, 1, 2), 1.0 / 6.0);
+        assert_close(wigner_6j(2, 2, 2, 2, 2, 2), 1.0 / 6.0);
+        assert_close(wigner_6j(4, 4, 4, 4, 4, 4), -3.0 / 70.0);
+        // broken triangles
+        assert_close(wigner_6j(2, 2, 6, 2, 2, 2), 0.0);
+        assert_close(wigner_6j(1, 1, 1, 1, 1, 1), 0.0);
+    }
+
+    #[test]
+    fn wigner_6j_is_orthogonal() {
+        // Σ_x (2x+1)(2f+1) {a b x; c d f}{a b x; c d f'} = δ_{ff'}
+        let (two_a, two_b, two_c, two_d) = (3, 2, 1, 4);
+        for two_f in (1..=7).step_by(2) {
+            for two_f_prime in (1..=7).step_by(2) {
+                let sum = (1..=5)
+                    .step_by(2)
+                    .map(|two_x: usize| {
+                        (two_x as f64 + 1.0)
+                            * (two_f as f64 + 1.0)
+                            * wigner_6j(two_a, two_b, two_x, two_c, two_d, two_f)
+                            * wigner_6j(two_a, two_b, two_x, two_c, two_d, two_f_prime)
+                    })
+                    .sum::<f64>();
+                let triangles = triangle(two_a, two_d, two_f) && triangle(two_c, two_b, two_f);
+                assert_close(
+                    sum,
+                    if two_f == two_f_prime && triangles {
+                        1.0
+                    } else {
+                        0.0
+                    },
+                );
+            }
+        }
+    }
+}